@@ -0,0 +1,271 @@
+use crate::error::StatusOrderError;
+use crate::{MonitoringStatus, Perfdata, PerfdataSet};
+use std::collections::HashMap;
+
+// Indexed by exit code
+const STATUSES: [MonitoringStatus; 4] = [
+    MonitoringStatus::OK,
+    MonitoringStatus::Warning,
+    MonitoringStatus::Critical,
+    MonitoringStatus::Unknown,
+];
+
+/// The strategy used by a [StatusPolicy] to aggregate the status of each [Perfdata] into the
+/// status of a [PerfdataSet]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Aggregation {
+    /// The worst status of all [Perfdata], according to the order of the [StatusPolicy]
+    #[default]
+    Worst,
+    /// The status of most [Perfdata], counted by their weight. Ties are resolved by taking the
+    /// worse status.
+    Majority,
+    /// Critical if the weight of critical [Perfdata] sums up to at least the given number, otherwise
+    /// those are considered Warning, and the worst status is taken. A number of 0 behaves like 1.
+    AtLeastCritical(usize),
+}
+
+/// Determines how [Perfdata] without a value (see [Perfdata::undetermined()]) contributes to the
+/// status of a [PerfdataSet]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum UndeterminedPolicy {
+    /// Undetermined values are not taken into account
+    #[default]
+    Ignore,
+    /// Undetermined values are [Unknown](`MonitoringStatus::Unknown`)
+    Unknown,
+    /// Undetermined values are [Critical](`MonitoringStatus::Critical`)
+    Critical,
+}
+
+/// `StatusPolicy` aggregates the status of the [Perfdata] of a [PerfdataSet] into a single
+/// [MonitoringStatus].
+///
+/// The default policy takes the worst status in the order of Icinga2, i.e. Critical is worse than
+/// Unknown is worse than Warning is worse than OK, ignores undetermined values and weights all
+/// [Perfdata] equally. This is what [PerfdataSet::status()] uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusPolicy {
+    aggregation: Aggregation,
+    order: [MonitoringStatus; 4],
+    undetermined: UndeterminedPolicy,
+    weights: HashMap<String, u32>,
+}
+
+impl Default for StatusPolicy {
+    fn default() -> Self {
+        StatusPolicy {
+            aggregation: Aggregation::default(),
+            order: StatusPolicy::ICINGA_ORDER,
+            undetermined: UndeterminedPolicy::default(),
+            weights: HashMap::new(),
+        }
+    }
+}
+
+impl StatusPolicy {
+    /// Order of statuses from best to worst as used by Icinga2, OK < Warning < Unknown < Critical
+    pub const ICINGA_ORDER: [MonitoringStatus; 4] = [
+        MonitoringStatus::OK,
+        MonitoringStatus::Warning,
+        MonitoringStatus::Unknown,
+        MonitoringStatus::Critical,
+    ];
+
+    /// Order of statuses from best to worst by their exit code, OK < Warning < Critical < Unknown,
+    /// like the `Ord` implementation of [MonitoringStatus]
+    pub const EXIT_CODE_ORDER: [MonitoringStatus; 4] = STATUSES;
+
+    /// Create the default `StatusPolicy`
+    pub fn new() -> Self {
+        StatusPolicy::default()
+    }
+
+    /// Set the [Aggregation] strategy
+    #[must_use]
+    pub fn with_aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Set the order of statuses, from best to worst. Fails if the order does not contain every
+    /// [MonitoringStatus].
+    pub fn with_order(mut self, order: [MonitoringStatus; 4]) -> Result<Self, StatusOrderError> {
+        if !STATUSES.iter().all(|s| order.contains(s)) {
+            return Err(StatusOrderError(order));
+        }
+        self.order = order;
+        Ok(self)
+    }
+
+    /// Set the [UndeterminedPolicy]
+    #[must_use]
+    pub fn with_undetermined(mut self, undetermined: UndeterminedPolicy) -> Self {
+        self.undetermined = undetermined;
+        self
+    }
+
+    /// Set the weight of the [Perfdata] with the given label, which is 1 by default. [Perfdata]
+    /// with a weight of 0 are not taken into account at all.
+    #[must_use]
+    pub fn with_weight<L: Into<String>>(mut self, label: L, weight: u32) -> Self {
+        self.weights.insert(label.into(), weight);
+        self
+    }
+
+    /// Returns the worse of both statuses according to the order of the `StatusPolicy`
+    pub fn worse(&self, a: MonitoringStatus, b: MonitoringStatus) -> MonitoringStatus {
+        if self.rank(b) > self.rank(a) {
+            b
+        } else {
+            a
+        }
+    }
+
+    /// The status of a single [Perfdata], `None` if it is not taken into account
+    pub fn perfdata_status(&self, pd: &Perfdata) -> Option<MonitoringStatus> {
+        if self.weight(pd.label()) == 0 {
+            return None;
+        }
+        match (pd.value(), self.undetermined) {
+            (Some(_), _) => Some(pd.status()),
+            (None, UndeterminedPolicy::Ignore) => None,
+            (None, UndeterminedPolicy::Unknown) => Some(MonitoringStatus::Unknown),
+            (None, UndeterminedPolicy::Critical) => Some(MonitoringStatus::Critical),
+        }
+    }
+
+    /// Aggregates the status of all [Perfdata] of the [PerfdataSet]. If no [Perfdata] is taken
+    /// into account, the status is [OK](`MonitoringStatus::OK`).
+    pub fn status(&self, pds: &PerfdataSet) -> MonitoringStatus {
+        let mut weights = [0u64; 4];
+        for pd in pds.data() {
+            if let Some(status) = self.perfdata_status(pd) {
+                weights[status.exit_code() as usize] += u64::from(self.weight(pd.label()));
+            }
+        }
+
+        match self.aggregation {
+            Aggregation::Worst => self.worst(&weights),
+            Aggregation::Majority => STATUSES
+                .into_iter()
+                .filter(|s| weights[s.exit_code() as usize] > 0)
+                .max_by_key(|s| (weights[s.exit_code() as usize], self.rank(*s)))
+                .unwrap_or(MonitoringStatus::OK),
+            Aggregation::AtLeastCritical(n) => {
+                let critical = MonitoringStatus::Critical.exit_code() as usize;
+                if weights[critical] >= n.max(1) as u64 {
+                    MonitoringStatus::Critical
+                } else {
+                    let warning = MonitoringStatus::Warning.exit_code() as usize;
+                    weights[warning] += weights[critical];
+                    weights[critical] = 0;
+                    self.worst(&weights)
+                }
+            }
+        }
+    }
+
+    fn worst(&self, weights: &[u64; 4]) -> MonitoringStatus {
+        self.order
+            .into_iter()
+            .rev()
+            .find(|s| weights[s.exit_code() as usize] > 0)
+            .unwrap_or(MonitoringStatus::OK)
+    }
+
+    fn rank(&self, status: MonitoringStatus) -> usize {
+        self.order
+            .iter()
+            .position(|s| *s == status)
+            .unwrap_or_default()
+    }
+
+    fn weight(&self, label: &str) -> u32 {
+        self.weights.get(label).copied().unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ThresholdRange;
+
+    fn pds() -> PerfdataSet<'static> {
+        let crit = ThresholdRange::above_pos(5);
+        vec![
+            Perfdata::unitless("ok", 1),
+            Perfdata::unitless("warn", 10).with_warn(crit),
+            Perfdata::unitless("warn2", 10).with_warn(crit),
+            Perfdata::unitless("crit", 10).with_crit(crit),
+            Perfdata::undetermined("undetermined"),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn test_worst() {
+        let policy = StatusPolicy::new();
+        let unknown = policy
+            .clone()
+            .with_undetermined(UndeterminedPolicy::Unknown);
+        let exit_code_order = unknown
+            .clone()
+            .with_order(StatusPolicy::EXIT_CODE_ORDER)
+            .unwrap();
+
+        assert_eq!(policy.status(&pds()), MonitoringStatus::Critical);
+        assert_eq!(policy.status(&PerfdataSet::new()), MonitoringStatus::OK);
+        assert_eq!(unknown.status(&pds()), MonitoringStatus::Critical);
+        assert_eq!(exit_code_order.status(&pds()), MonitoringStatus::Unknown);
+        assert_eq!(
+            policy.with_weight("crit", 0).status(&pds()),
+            MonitoringStatus::Warning
+        );
+        assert_eq!(
+            unknown.worse(MonitoringStatus::Unknown, MonitoringStatus::Critical),
+            MonitoringStatus::Critical
+        );
+    }
+
+    #[test]
+    fn test_majority() {
+        let policy = StatusPolicy::new().with_aggregation(Aggregation::Majority);
+
+        assert_eq!(policy.status(&pds()), MonitoringStatus::Warning);
+        assert_eq!(
+            policy.clone().with_weight("crit", 2).status(&pds()),
+            MonitoringStatus::Critical
+        );
+        assert_eq!(
+            policy.with_weight("ok", 3).status(&pds()),
+            MonitoringStatus::OK
+        );
+    }
+
+    #[test]
+    fn test_at_least_critical() {
+        let two = StatusPolicy::new().with_aggregation(Aggregation::AtLeastCritical(2));
+        let undetermined = two.clone().with_undetermined(UndeterminedPolicy::Critical);
+
+        assert_eq!(two.status(&pds()), MonitoringStatus::Warning);
+        assert_eq!(undetermined.status(&pds()), MonitoringStatus::Critical);
+        assert_eq!(
+            two.with_weight("crit", 2).status(&pds()),
+            MonitoringStatus::Critical
+        );
+    }
+
+    #[test]
+    fn test_invalid_order() {
+        let err = StatusPolicy::new()
+            .with_order([MonitoringStatus::OK; 4])
+            .unwrap_err();
+
+        assert_eq!(err.order(), [MonitoringStatus::OK; 4]);
+        assert_eq!(
+            err.to_string(),
+            "order must contain every status, got [OK, OK, OK, OK]"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::monitoring_status::MonitoringStatus;
+use crate::{NumberFormat, Perfdata, PerfdataSet, PluginOutput};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+
+/// A `CheckResult` is the result of a monitoring check, ready to be reported to the monitoring
+/// engine. It consists of a [PluginOutput] and a [MonitoringStatus].
+///
+/// Unless set explicitly with [with_status()](`Self::with_status()`), the status is derived from
+/// the thresholds of the contained [Perfdata] (see [PerfdataSet::status()]).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, PartialEq)]
+pub struct CheckResult<'a> {
+    status: Option<MonitoringStatus>,
+    output: PluginOutput<'a>,
+}
+
+impl<'a> CheckResult<'a> {
+    /// Create a new `CheckResult` with the given summary text
+    pub fn new<T: Into<Cow<'a, str>>>(summary: T) -> Self {
+        CheckResult {
+            status: None,
+            output: PluginOutput::new(summary),
+        }
+    }
+
+    /// Override the [MonitoringStatus] derived from the thresholds
+    #[must_use]
+    pub fn with_status(mut self, status: MonitoringStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Add a line of long output to the `CheckResult`
+    #[must_use]
+    pub fn with_long_text<T: Into<Cow<'a, str>>>(mut self, line: T) -> Self {
+        self.add_long_text(line);
+        self
+    }
+
+    /// Add [Perfdata] to the `CheckResult`
+    #[must_use]
+    pub fn with_perfdata(mut self, pd: Perfdata<'a>) -> Self {
+        self.add_perfdata(pd);
+        self
+    }
+
+    /// Add all [Perfdata] of a [PerfdataSet] to the `CheckResult`
+    #[must_use]
+    pub fn with_perfdata_set(mut self, pds: PerfdataSet<'a>) -> Self {
+        for pd in pds {
+            self.add_perfdata(pd);
+        }
+        self
+    }
+
+    /// Add a line of long output to the `CheckResult`
+    pub fn add_long_text<T: Into<Cow<'a, str>>>(&mut self, line: T) {
+        self.output.add_long_text(line);
+    }
+
+    /// Add [Perfdata] to the `CheckResult`
+    pub fn add_perfdata(&mut self, pd: Perfdata<'a>) {
+        self.output.add_perfdata(pd);
+    }
+
+    /// The [MonitoringStatus] of the `CheckResult`, either set explicitly or derived from the
+    /// thresholds of the contained [Perfdata]
+    pub fn status(&self) -> MonitoringStatus {
+        self.status
+            .unwrap_or_else(|| self.output.perfdata().status())
+    }
+
+    /// The [PluginOutput] which will be reported to the monitoring engine
+    pub fn output(&self) -> &PluginOutput<'a> {
+        &self.output
+    }
+
+    /// Print the [PluginOutput] to stdout, and exit the process with the exit code of the
+    /// [MonitoringStatus]. This is usually the last call in a monitoring check.
+    pub fn exit(&self) -> ! {
+        println!("{}", self);
+        std::process::exit(self.status().exit_code())
+    }
+
+    /// Like [exit()](`Self::exit()`), but writes the perfdata with the given [NumberFormat]
+    pub fn exit_with(&self, format: NumberFormat) -> ! {
+        println!("{}", self.display_with(format));
+        std::process::exit(self.status().exit_code())
+    }
+}
+
+impl Display for CheckResult<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ThresholdRange;
+
+    #[test]
+    fn test_derived_status() {
+        let ok = CheckResult::new("ok").with_perfdata(Perfdata::unitless("ok", 1));
+        let warn = CheckResult::new("warn")
+            .with_perfdata(Perfdata::unitless("ok", 1))
+            .with_perfdata(Perfdata::unitless("warn", 10).with_warn(ThresholdRange::above_pos(5)));
+        let crit = CheckResult::new("crit").with_perfdata_set(
+            vec![
+                Perfdata::unitless("warn", 10).with_warn(ThresholdRange::above_pos(5)),
+                Perfdata::unitless("crit", 10).with_crit(ThresholdRange::above_pos(5)),
+            ]
+            .into(),
+        );
+        let empty = CheckResult::new("empty");
+
+        assert_eq!(ok.status(), MonitoringStatus::OK);
+        assert_eq!(warn.status(), MonitoringStatus::Warning);
+        assert_eq!(crit.status(), MonitoringStatus::Critical);
+        assert_eq!(empty.status(), MonitoringStatus::OK);
+    }
+
+    #[test]
+    fn test_status_override() {
+        let unknown = CheckResult::new("could not connect").with_status(MonitoringStatus::Unknown);
+        let ok = CheckResult::new("ignored")
+            .with_perfdata(Perfdata::unitless("crit", 10).with_crit(ThresholdRange::above_pos(5)))
+            .with_status(MonitoringStatus::OK);
+
+        assert_eq!(unknown.status(), MonitoringStatus::Unknown);
+        assert_eq!(ok.status(), MonitoringStatus::OK);
+    }
+
+    #[test]
+    fn test_format() {
+        let mut result = CheckResult::new("DISK WARNING - / is 91% full")
+            .with_perfdata(Perfdata::percentage("/", 91).with_warn(ThresholdRange::above_pos(90)))
+            .with_long_text("/ 91%");
+        result.add_long_text(format!("/boot {}%", 20));
+        result.add_perfdata(Perfdata::percentage("/boot", 20));
+
+        let formatted = result.to_string();
+
+        assert_eq!(
+            formatted,
+            "DISK WARNING - / is 91% full | '/'=91%;90;;;; '/boot'=20%;\n/ 91%\n/boot 20%"
+        );
+        assert_eq!(
+            PluginOutput::try_from(formatted.as_str()).unwrap(),
+            *result.output()
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        let result = CheckResult::new("LOAD OK")
+            .with_perfdata(Perfdata::unitless("load1", 2.0 / 3.0))
+            .with_long_text("load1 is 0.67");
+        let format = NumberFormat::new().with_max_decimals(2);
+
+        assert_eq!(
+            result.display_with(format).to_string(),
+            "LOAD OK | 'load1'=0.67;\nload1 is 0.67"
+        );
+        assert_eq!(
+            result.output().display_with(format).to_string(),
+            result.display_with(format).to_string()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let result = CheckResult::new("LOAD OK")
+            .with_status(MonitoringStatus::Warning)
+            .with_long_text("load1 is 4")
+            .with_perfdata(Perfdata::unitless("load1", 4).with_warn(ThresholdRange::above_pos(5)));
+
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed: CheckResult = serde_json::from_reader(json.as_bytes()).unwrap();
+        let bytes = bincode::serialize(&result).unwrap();
+
+        assert_eq!(parsed, result);
+        assert_eq!(bincode::deserialize::<CheckResult>(&bytes).unwrap(), result);
+    }
+}
@@ -1,20 +1,403 @@
+use crate::{MonitoringStatus, Unit};
+use std::fmt::{Display, Formatter};
 use std::num::ParseFloatError;
+use std::ops::Range;
 use thiserror::Error;
 
-#[derive(Error, Debug, PartialEq)]
-pub enum PerfdataParseError {
+/// The kind of error that occurred while parsing perfdata
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PerfdataParseErrorKind {
+    /// The label is not separated from the data by an equals sign
     #[error("equals sign (=) must be used to separate the label from data")]
     MissingEqualsSign,
+    /// There is no value after the equals sign
     #[error("numerical value missing after equals sign")]
     MissingValue,
+    /// There is no label before the equals sign
     #[error("label missing before equals sign")]
     MissingLabel,
+    /// A value, threshold or limit is not a number
     #[error("value is not a number")]
     ParseValueError(#[from] ParseFloatError),
+    /// A threshold is empty
     #[error("threshold may not be empty for parsing")]
     ThresholdEmpty,
-    #[error("unknown unit `{0}`")]
-    UnknownUnit(String),
-    #[error("labels must not contain the single quote `'` character")]
+    /// The unit of measurement contains forbidden characters
+    #[error("invalid unit of measurement `{0}`")]
+    InvalidUnit(String),
+    /// The label contains a single quote, which is not escaped
+    #[error("labels must not contain the single quote `'` character, unless escaped as `''` in a quoted label")]
     LabelContainsSingleQuote,
+    /// The perfdata could be parsed, but is not valid, which is only checked by strict parsing
+    #[error(transparent)]
+    Invalid(#[from] PerfdataViolation),
+}
+
+/// A violation of the rules for valid perfdata, as reported by
+/// [Perfdata::validate()](`crate::Perfdata::validate()`)
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PerfdataViolation {
+    /// A value, threshold or limit is NaN, or infinite where it is not allowed to be
+    #[error("{0} is not a finite number")]
+    NotFinite(PerfdataField),
+    /// The minimum is greater than the maximum
+    #[error("min {min} is greater than max {max}")]
+    MinAboveMax {
+        /// The minimum value
+        min: f64,
+        /// The maximum value
+        max: f64,
+    },
+    /// The value is less than the minimum
+    #[error("value {value} is less than min {min}")]
+    BelowMin {
+        /// The value
+        value: f64,
+        /// The minimum value
+        min: f64,
+    },
+    /// The value is greater than the maximum
+    #[error("value {value} is greater than max {max}")]
+    AboveMax {
+        /// The value
+        value: f64,
+        /// The maximum value
+        max: f64,
+    },
+    /// A percentage is not within 0 and 100
+    #[error("percentage {value} in {field} is not within 0 and 100")]
+    PercentageOutOfRange {
+        /// The value, min or max
+        field: PerfdataField,
+        /// The percentage
+        value: f64,
+    },
+    /// The value of a counter is negative
+    #[error("counter value {0} is negative")]
+    NegativeCounter(f64),
+}
+
+impl PerfdataViolation {
+    /// The part of the perfdata which violates the rules
+    pub fn field(&self) -> PerfdataField {
+        match self {
+            PerfdataViolation::NotFinite(field) => *field,
+            PerfdataViolation::MinAboveMax { .. } => PerfdataField::Min,
+            PerfdataViolation::BelowMin { .. } => PerfdataField::Value,
+            PerfdataViolation::AboveMax { .. } => PerfdataField::Value,
+            PerfdataViolation::PercentageOutOfRange { field, .. } => *field,
+            PerfdataViolation::NegativeCounter(_) => PerfdataField::Value,
+        }
+    }
+}
+
+/// The part of a [Perfdata](`crate::Perfdata`) in which a parse error occurred
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerfdataField {
+    /// The label before the equals sign
+    Label,
+    /// The numerical value
+    Value,
+    /// The unit of measurement following the value
+    Unit,
+    /// The warning threshold
+    Warn,
+    /// The critical threshold
+    Crit,
+    /// The minimum value
+    Min,
+    /// The maximum value
+    Max,
+}
+
+impl Display for PerfdataField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let word = match self {
+            PerfdataField::Label => "label",
+            PerfdataField::Value => "value",
+            PerfdataField::Unit => "unit",
+            PerfdataField::Warn => "warn",
+            PerfdataField::Crit => "crit",
+            PerfdataField::Min => "min",
+            PerfdataField::Max => "max",
+        };
+        f.write_str(word)
+    }
+}
+
+/// Error returned when parsing perfdata fails.
+///
+/// Besides the [kind](`Self::kind()`) of the error, it points to the offending `token` by its
+/// byte offsets in the parsed input, and if applicable to the [PerfdataField] and the index of the
+/// item in a [PerfdataSet](`crate::PerfdataSet`) which failed to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerfdataParseError {
+    kind: PerfdataParseErrorKind,
+    field: Option<PerfdataField>,
+    span: Range<usize>,
+    token: String,
+    item: Option<usize>,
+}
+
+impl PerfdataParseError {
+    // The token must be a subslice of the input to determine its position, otherwise the span is
+    // empty
+    pub(crate) fn new(kind: PerfdataParseErrorKind, input: &str, token: &str) -> Self {
+        let span = offset_of(input, token).map_or(0..0, |start| start..start + token.len());
+        PerfdataParseError {
+            kind,
+            field: None,
+            span,
+            token: token.to_string(),
+            item: None,
+        }
+    }
+
+    pub(crate) fn with_field(mut self, field: PerfdataField) -> Self {
+        self.field.get_or_insert(field);
+        self
+    }
+
+    // Moves the span, when the input was part of a larger input
+    pub(crate) fn with_offset(mut self, offset: usize) -> Self {
+        self.span = self.span.start + offset..self.span.end + offset;
+        self
+    }
+
+    // Items are counted from the start of the larger input, when the input was part of it
+    pub(crate) fn with_item(mut self, index: usize) -> Self {
+        self.item = Some(self.item.unwrap_or_default() + index);
+        self
+    }
+
+    /// The kind of error that occurred
+    pub fn kind(&self) -> &PerfdataParseErrorKind {
+        &self.kind
+    }
+
+    /// The part of the perfdata which could not be parsed, if the error is specific to one
+    pub fn field(&self) -> Option<PerfdataField> {
+        self.field
+    }
+
+    /// Byte offsets of the offending token in the parsed input
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The offending token
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The index of the failing item among all perfdata, when parsing multiple perfdata. Text
+    /// which is skipped as trailing garbage (see [ParseOptions](`crate::ParseOptions`)) is not
+    /// counted.
+    pub fn item(&self) -> Option<usize> {
+        self.item
+    }
+}
+
+// The byte offset of the part in the input, if it is a subslice of the input
+pub(crate) fn offset_of(input: &str, part: &str) -> Option<usize> {
+    let offset = (part.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    (offset + part.len() <= input.len()).then_some(offset)
+}
+
+impl Display for PerfdataParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(item) = self.item {
+            write!(f, "perfdata #{}: ", item)?;
+        }
+        write!(f, "{}", self.kind)?;
+        if let Some(field) = self.field {
+            write!(f, " in {}", field)?;
+        }
+        write!(
+            f,
+            " at {}..{}: `{}`",
+            self.span.start, self.span.end, self.token
+        )
+    }
+}
+
+impl std::error::Error for PerfdataParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+/// Error returned when parsing a [MonitoringStatus](`crate::MonitoringStatus`) from a string fails
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown monitoring status `{0}`")]
+pub struct ParseMonitoringStatusError(pub(crate) String);
+
+/// Error returned when a unit of measurement contains characters which are not allowed in
+/// perfdata, like digits, whitespace, semicolons or quotes
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid unit of measurement `{0}`")]
+pub struct InvalidUnitError(pub(crate) String);
+
+/// Error returned when converting a value between [Unit]s of different quantities, e.g. seconds
+/// into bytes
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("can't convert unit {from:?} into {to:?}")]
+pub struct UnitConversionError {
+    pub(crate) from: Unit,
+    pub(crate) to: Unit,
+}
+
+impl UnitConversionError {
+    /// The unit of the value
+    pub fn from(&self) -> &Unit {
+        &self.from
+    }
+
+    /// The target unit of the conversion
+    pub fn to(&self) -> &Unit {
+        &self.to
+    }
+}
+
+/// Error returned when an order of statuses passed to
+/// [StatusPolicy::with_order()](`crate::StatusPolicy::with_order()`) does not contain every
+/// [MonitoringStatus]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("order must contain every status, got {0:?}")]
+pub struct StatusOrderError(pub(crate) [MonitoringStatus; 4]);
+
+impl StatusOrderError {
+    /// The invalid order
+    pub fn order(&self) -> [MonitoringStatus; 4] {
+        self.0
+    }
+}
+
+/// Error returned when parsing a [HostStatus](`crate::HostStatus`) from a string fails
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown host status `{0}`")]
+pub struct ParseHostStatusError(pub(crate) String);
+
+/// Error returned when parsing a [PerfdataRecord](`crate::PerfdataRecord`) fails
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PerfdataRecordError {
+    /// A required field is missing or empty
+    #[error("required field `{0}` is missing")]
+    MissingField(&'static str),
+    /// A field is not of the form `KEY::value`
+    #[error("field `{0}` must be of the form `KEY::value`")]
+    InvalidField(String),
+    /// The `DATATYPE` is neither `HOSTPERFDATA` nor `SERVICEPERFDATA`
+    #[error("unknown data type `{0}`")]
+    InvalidDataType(String),
+    /// The `TIMET` is not a unix timestamp
+    #[error("invalid timestamp `{0}`")]
+    InvalidTimestamp(String),
+    /// The perfdata could not be parsed, its span is relative to the start of the record
+    #[error("invalid perfdata: {0}")]
+    Perfdata(#[from] PerfdataParseError),
+}
+
+/// Error returned when parsing an [InfluxPoint](`crate::InfluxPoint`) from line protocol fails
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum InfluxParseError {
+    /// The line does not contain a measurement and fields, separated by a space
+    #[error("line must contain a measurement and fields separated by a space")]
+    MissingFields,
+    /// A required tag is missing
+    #[error("required tag `{0}` is missing")]
+    MissingTag(&'static str),
+    /// A tag is not of the form `key=value`
+    #[error("tag `{0}` must be of the form `key=value`")]
+    InvalidTag(String),
+    /// A field is not of the form `key=value`, or its value is not a number or boolean
+    #[error("invalid field `{0}`")]
+    InvalidField(String),
+    /// The timestamp is not an integer
+    #[error("invalid timestamp `{0}`")]
+    InvalidTimestamp(String),
+    /// The unit tag is not a valid unit of measurement
+    #[error(transparent)]
+    InvalidUnit(#[from] InvalidUnitError),
+}
+
+/// Error returned when parsing OpenMetrics with [OpenMetrics::parse()](`crate::OpenMetrics::parse()`)
+/// fails
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum OpenMetricsParseError {
+    /// The line is neither a comment nor a sample with a valid name and value
+    #[error("line {line}: invalid sample `{sample}`")]
+    InvalidSample {
+        /// The number of the line, starting at 1
+        line: usize,
+        /// The invalid sample
+        sample: String,
+    },
+    /// The value of a sample is not a number
+    #[error("line {line}: value `{value}` is not a number")]
+    InvalidValue {
+        /// The number of the line, starting at 1
+        line: usize,
+        /// The invalid value
+        value: String,
+    },
+}
+
+/// Error returned when reading perfdata line by line from a [BufRead](`std::io::BufRead`) fails
+#[derive(Error, Debug)]
+pub enum PerfdataReadError {
+    /// Reading the line failed
+    #[error("failed to read line {line}: {source}")]
+    Io {
+        /// The number of the line, starting at 1
+        line: usize,
+        /// The underlying I/O error
+        source: std::io::Error,
+    },
+    /// The line could not be parsed
+    #[error("line {line}: {source}")]
+    Parse {
+        /// The number of the line, starting at 1
+        line: usize,
+        /// The parse error, with its span relative to the start of the line
+        source: PerfdataParseError,
+    },
+    /// The line is not a valid [PerfdataRecord](`crate::PerfdataRecord`)
+    #[error("line {line}: {source}")]
+    Record {
+        /// The number of the line, starting at 1
+        line: usize,
+        /// The record error
+        source: PerfdataRecordError,
+    },
+}
+
+impl PerfdataReadError {
+    /// The number of the line which failed, starting at 1
+    pub fn line(&self) -> usize {
+        match self {
+            PerfdataReadError::Io { line, .. }
+            | PerfdataReadError::Parse { line, .. }
+            | PerfdataReadError::Record { line, .. } => *line,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_of() {
+        let input = "label=1;2;3";
+        let other = String::from("1;2");
+
+        assert_eq!(offset_of(input, &input[6..9]), Some(6));
+        assert_eq!(offset_of(input, &input[11..]), Some(11));
+        assert_eq!(offset_of(&input[6..], input), None);
+        assert_eq!(offset_of(input, &other), None);
+
+        let err = PerfdataParseError::new(PerfdataParseErrorKind::MissingValue, input, &other);
+        assert_eq!(err.span(), 0..0);
+    }
 }
@@ -0,0 +1,276 @@
+use crate::{Perfdata, PerfdataRecord, RecordKind, ThresholdRange};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+// Source: https://icinga.com/docs/icinga-2/latest/doc/14-features/#graphite-writer
+// Metrics are sent in the carbon plaintext protocol, one per line:
+// <prefix>.perfdata.<label>.<value|warn|crit|min|max> <value> <timestamp>
+// Notes:
+//     the prefix is built from a template for host or service records. Dots, spaces, slashes and
+//     backslashes in labels and macro values are replaced by underscores, as they would create
+//     additional levels in the metric path. `::` in labels is kept as level separator.
+
+const DEFAULT_HOST_TEMPLATE: &str = "icinga2.$host.name$.host.$host.check_command$";
+const DEFAULT_SERVICE_TEMPLATE: &str =
+    "icinga2.$host.name$.services.$service.name$.$service.check_command$";
+const PATH_SEPARATOR: &str = ".";
+const LABEL_SEPARATOR: &str = "::";
+const FORBIDDEN: [char; 4] = ['.', ' ', '/', '\\'];
+const REPLACEMENT: &str = "_";
+
+/// A single metric in the Graphite plaintext protocol, formatted as `path value timestamp`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphiteMetric {
+    path: String,
+    value: f64,
+    timestamp: i64,
+}
+
+impl GraphiteMetric {
+    /// The dot separated metric path
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The value of the metric
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// The unix timestamp of the metric
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+impl Display for GraphiteMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.path, self.value, self.timestamp)
+    }
+}
+
+/// `GraphiteExporter` turns the [PerfdataSet](`crate::PerfdataSet`) of a [PerfdataRecord] into
+/// metrics for Graphite, the way the GraphiteWriter of Icinga2 does.
+///
+/// The prefix of the metric paths is built from a template for host or service records, in which
+/// the macros `$host.name$`, `$service.name$`, `$host.check_command$` and
+/// `$service.check_command$` are replaced. Empty levels of the path are left out.
+///
+/// For each [Perfdata] the `.value` is exported, and unless disabled its `.warn`, `.crit`, `.min`
+/// and `.max`. A [ThresholdRange] is exported as its upper bound, or as its lower bound when it is
+/// unbounded above. Values which are undetermined or not finite are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphiteExporter {
+    host_template: String,
+    service_template: String,
+    thresholds: bool,
+}
+
+impl Default for GraphiteExporter {
+    fn default() -> Self {
+        GraphiteExporter {
+            host_template: DEFAULT_HOST_TEMPLATE.to_string(),
+            service_template: DEFAULT_SERVICE_TEMPLATE.to_string(),
+            thresholds: true,
+        }
+    }
+}
+
+impl GraphiteExporter {
+    /// Create a new `GraphiteExporter` with the default templates of Icinga2
+    pub fn new() -> Self {
+        GraphiteExporter::default()
+    }
+
+    /// Set the template for metric paths of host records
+    #[must_use]
+    pub fn with_host_template<T: Into<String>>(mut self, template: T) -> Self {
+        self.host_template = template.into();
+        self
+    }
+
+    /// Set the template for metric paths of service records
+    #[must_use]
+    pub fn with_service_template<T: Into<String>>(mut self, template: T) -> Self {
+        self.service_template = template.into();
+        self
+    }
+
+    /// Whether to export thresholds, minimum and maximum besides the value
+    #[must_use]
+    pub fn with_thresholds(mut self, thresholds: bool) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Returns the metrics for all perfdata of the record
+    pub fn metrics(&self, record: &PerfdataRecord) -> Vec<GraphiteMetric> {
+        let prefix = self.prefix(record);
+        record
+            .perfdata()
+            .data()
+            .flat_map(|pd| self.perfdata_metrics(&prefix, pd))
+            .map(|(path, value)| GraphiteMetric {
+                path,
+                value,
+                timestamp: record.timestamp(),
+            })
+            .collect()
+    }
+
+    /// Writes the metrics for all perfdata of the record as lines, e.g. to a
+    /// [TcpStream](`std::net::TcpStream`) connected to carbon
+    pub fn write<W: Write>(&self, writer: &mut W, record: &PerfdataRecord) -> std::io::Result<()> {
+        for metric in self.metrics(record) {
+            writeln!(writer, "{}", metric)?;
+        }
+        Ok(())
+    }
+
+    fn prefix(&self, record: &PerfdataRecord) -> String {
+        let (template, check_command_macro) = match record.kind() {
+            RecordKind::Host => (&self.host_template, "$host.check_command$"),
+            RecordKind::Service => (&self.service_template, "$service.check_command$"),
+        };
+        let macros = [
+            ("$host.name$", Some(record.host_name())),
+            ("$service.name$", record.service_description()),
+            (check_command_macro, record.check_command()),
+        ];
+
+        let mut prefix = template.clone();
+        for (name, value) in macros {
+            prefix = prefix.replace(name, &sanitize(value.unwrap_or_default()));
+        }
+        // Macros of the other object kind are not available
+        for name in ["$host.check_command$", "$service.check_command$"] {
+            prefix = prefix.replace(name, "");
+        }
+
+        prefix
+            .split(PATH_SEPARATOR)
+            .filter(|level| !level.is_empty())
+            .collect::<Vec<_>>()
+            .join(PATH_SEPARATOR)
+    }
+
+    fn perfdata_metrics(&self, prefix: &str, pd: &Perfdata) -> Vec<(String, f64)> {
+        let label = pd
+            .label()
+            .split(LABEL_SEPARATOR)
+            .map(sanitize)
+            .collect::<Vec<_>>()
+            .join(PATH_SEPARATOR);
+
+        let mut values = vec![("value", pd.value())];
+        if self.thresholds {
+            values.extend([
+                ("warn", pd.warn().map(threshold_value)),
+                ("crit", pd.crit().map(threshold_value)),
+                ("min", pd.min()),
+                ("max", pd.max()),
+            ]);
+        }
+
+        values
+            .into_iter()
+            .filter_map(|(name, value)| value.filter(|v| v.is_finite()).map(|v| (name, v)))
+            .map(|(name, value)| (format!("{}.perfdata.{}.{}", prefix, label, name), value))
+            .collect()
+    }
+}
+
+fn sanitize(s: &str) -> String {
+    s.replace(FORBIDDEN, REPLACEMENT)
+}
+
+fn threshold_value(range: ThresholdRange) -> f64 {
+    if range.end().is_finite() {
+        range.end()
+    } else {
+        range.start()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PerfdataSet;
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+
+    fn disk_record() -> PerfdataRecord<'static> {
+        let pds: PerfdataSet = vec![
+            Perfdata::megabytes("/var/log", 818)
+                .with_warn(ThresholdRange::above_pos(970))
+                .with_crit(ThresholdRange::outside(10, 975))
+                .with_min(0),
+            Perfdata::undetermined("inodes::used"),
+            Perfdata::percentage("inodes::free", 80),
+        ]
+        .into();
+        PerfdataRecord::service(1700000000, "web01.example.com", "disk space")
+            .with_check_command("disk")
+            .with_perfdata(pds)
+    }
+
+    #[test]
+    fn test_metrics() {
+        let lines: Vec<String> = GraphiteExporter::new()
+            .metrics(&disk_record())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        let prefix = "icinga2.web01_example_com.services.disk_space.disk.perfdata";
+        assert_eq!(
+            lines,
+            [
+                format!("{}._var_log.value 818 1700000000", prefix),
+                format!("{}._var_log.warn 970 1700000000", prefix),
+                format!("{}._var_log.crit 975 1700000000", prefix),
+                format!("{}._var_log.min 0 1700000000", prefix),
+                format!("{}.inodes.free.value 80 1700000000", prefix),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_templates() {
+        let host = PerfdataRecord::host(1, "web01")
+            .with_perfdata(vec![Perfdata::seconds("rta", 0.5).with_max(10)].into());
+        let exporter = GraphiteExporter::new()
+            .with_host_template("nagios.$host.name$.$service.name$.$host.check_command$")
+            .with_thresholds(false);
+
+        let paths: Vec<String> = exporter
+            .metrics(&host)
+            .into_iter()
+            .map(|m| m.path().to_string())
+            .collect();
+
+        assert_eq!(paths, ["nagios.web01.perfdata.rta.value"]);
+    }
+
+    #[test]
+    fn test_write_to_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = String::new();
+            stream.read_to_string(&mut received).unwrap();
+            received
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        GraphiteExporter::new()
+            .write(&mut stream, &disk_record())
+            .unwrap();
+        drop(stream);
+        let received = server.join().unwrap();
+
+        assert_eq!(received.lines().count(), 5);
+        assert!(received.ends_with(".inodes.free.value 80 1700000000\n"));
+    }
+}
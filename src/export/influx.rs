@@ -0,0 +1,510 @@
+use crate::error::InfluxParseError;
+use crate::{Perfdata, PerfdataRecord, RecordKind, ThresholdRange};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+
+// Source: https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/
+// Each perfdata item is written as one point:
+// <measurement>,hostname=<host>,metric=<label>[,service=<desc>][,unit=<uom>] <fields> <timestamp>
+// Fields are `value`, `min` and `max`, and for each threshold either:
+//     warn=<end>
+//         for thresholds from 0 to end, e.g. `10`
+//     warn_min=<start>,warn_max=<end>[,warn_inside=true]
+//         for any other range, infinite bounds are left out
+// Notes:
+//     the timestamp is in nanoseconds, and left out if the record's timestamp doesn't fit. Commas,
+//     spaces and backslashes are escaped with a backslash in the measurement, and additionally
+//     equals signs in tag keys, tag values and field keys. Quoted strings are only recognized in
+//     the fields, a `"` in the measurement or tags is literal.
+
+const DEFAULT_MEASUREMENT: &str = "perfdata";
+const TAG_HOST: &str = "hostname";
+const TAG_LABEL: &str = "metric";
+const TAG_SERVICE: &str = "service";
+const TAG_UNIT: &str = "unit";
+const ESCAPE: char = '\\';
+const MEASUREMENT_SPECIAL: [char; 3] = [',', ' ', ESCAPE];
+const KEY_SPECIAL: [char; 4] = [',', '=', ' ', ESCAPE];
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+/// `InfluxPoint` is a single [Perfdata] of a host or service, in the
+/// [InfluxDB line protocol](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/).
+///
+/// It is created by an [InfluxExporter], and can be parsed back from a line with `from_str()`.
+/// Tags and fields are named like those of the InfluxdbWriter of Icinga2, thresholds which are not
+/// a simple upper limit are split into `warn_min`, `warn_max` and `warn_inside` fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InfluxPoint {
+    measurement: String,
+    host: String,
+    service: Option<String>,
+    timestamp: Option<i64>,
+    perfdata: Perfdata<'static>,
+}
+
+impl InfluxPoint {
+    /// The name of the measurement, usually the check command
+    pub fn measurement(&self) -> &str {
+        &self.measurement
+    }
+
+    /// The name of the host
+    pub fn host_name(&self) -> &str {
+        &self.host
+    }
+
+    /// The description of the service, if the point belongs to one
+    pub fn service_description(&self) -> Option<&str> {
+        self.service.as_deref()
+    }
+
+    /// The timestamp in nanoseconds, if present
+    pub fn timestamp(&self) -> Option<i64> {
+        self.timestamp
+    }
+
+    /// The [Perfdata] of the point
+    pub fn perfdata(&self) -> &Perfdata<'static> {
+        &self.perfdata
+    }
+
+    /// Converts the point into its [Perfdata]
+    pub fn into_perfdata(self) -> Perfdata<'static> {
+        self.perfdata
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        let pd = &self.perfdata;
+        let mut fields = Vec::new();
+        let mut add_number = |key: String, value: Option<f64>| {
+            if let Some(value) = value.filter(|v| v.is_finite()) {
+                fields.push((key, value.to_string()));
+            }
+        };
+
+        add_number("value".to_string(), pd.value());
+        let mut inside = Vec::new();
+        for (name, threshold) in [("warn", pd.warn()), ("crit", pd.crit())] {
+            match threshold.map(|t| (t, t.upper_limit())) {
+                Some((_, Some(limit))) => add_number(name.to_string(), Some(limit)),
+                Some((t, None)) => {
+                    add_number(format!("{}_min", name), Some(t.start()));
+                    add_number(format!("{}_max", name), Some(t.end()));
+                    if t.alert_inside() {
+                        inside.push(format!("{}_inside", name));
+                    }
+                }
+                None => {}
+            }
+        }
+        add_number("min".to_string(), pd.min());
+        add_number("max".to_string(), pd.max());
+
+        fields.extend(inside.into_iter().map(|key| (key, "true".to_string())));
+        fields
+    }
+}
+
+impl Display for InfluxPoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", escape(&self.measurement, &MEASUREMENT_SPECIAL))?;
+
+        let tags = [
+            (TAG_HOST, Some(self.host.as_str())),
+            (TAG_LABEL, Some(self.perfdata.label())),
+            (TAG_SERVICE, self.service.as_deref()),
+            (TAG_UNIT, Some(self.perfdata.uom())),
+        ];
+        for (key, value) in tags {
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                write!(f, ",{}={}", key, escape(value, &KEY_SPECIAL))?;
+            }
+        }
+
+        for (idx, (key, value)) in self.fields().iter().enumerate() {
+            let separator = if idx == 0 { ' ' } else { ',' };
+            write!(f, "{}{}={}", separator, escape(key, &KEY_SPECIAL), value)?;
+        }
+
+        if let Some(timestamp) = self.timestamp {
+            write!(f, " {}", timestamp)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for InfluxPoint {
+    type Err = InfluxParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s.trim();
+        let series = split_unescaped(line, ' ', false)[0];
+        let rest = line
+            .get(series.len() + 1..)
+            .ok_or(InfluxParseError::MissingFields)?;
+        let (fields, timestamp) = match split_unescaped(rest, ' ', true).as_slice() {
+            [fields] => (*fields, None),
+            [fields, timestamp] => (*fields, Some(*timestamp)),
+            _ => return Err(InfluxParseError::MissingFields),
+        };
+
+        let mut series = split_unescaped(series, ',', false).into_iter();
+        let measurement = unescape(series.next().unwrap_or_default());
+        let mut tags = Vec::new();
+        for tag in series {
+            let (key, value) =
+                split_pair(tag).ok_or_else(|| InfluxParseError::InvalidTag(tag.to_string()))?;
+            tags.push((unescape(key), unescape(value)));
+        }
+        let tag = |key: &str| tags.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        let require = |key: &'static str| tag(key).ok_or(InfluxParseError::MissingTag(key));
+
+        let mut fields_by_key = Vec::new();
+        for field in split_unescaped(fields, ',', true) {
+            let (key, value) = split_pair(field)
+                .ok_or_else(|| InfluxParseError::InvalidField(field.to_string()))?;
+            fields_by_key.push((unescape(key), value));
+        }
+        let field = |key: &str| {
+            fields_by_key
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| *v)
+        };
+        let number = |key: &str| field(key).map(parse_number).transpose();
+        let threshold = |name: &str| -> Result<Option<ThresholdRange>, InfluxParseError> {
+            if let Some(end) = number(name)? {
+                return Ok(Some(ThresholdRange::above_pos(end)));
+            }
+            let start = number(&format!("{}_min", name))?;
+            let end = number(&format!("{}_max", name))?;
+            if start.is_none() && end.is_none() {
+                return Ok(None);
+            }
+            let start = start.unwrap_or(f64::NEG_INFINITY);
+            let end = end.unwrap_or(f64::INFINITY);
+            let inside = field(&format!("{}_inside", name)).map_or(Ok(false), parse_bool)?;
+            if inside {
+                Ok(Some(ThresholdRange::inside(start, end)))
+            } else {
+                Ok(Some(ThresholdRange::outside(start, end)))
+            }
+        };
+
+        let label = require(TAG_LABEL)?;
+        let mut perfdata = match number("value")? {
+            Some(value) => Perfdata::custom(label, value, &tag(TAG_UNIT).unwrap_or_default())?,
+            None => Perfdata::undetermined(label),
+        };
+        if let Some(warn) = threshold("warn")? {
+            perfdata = perfdata.with_warn(warn);
+        }
+        if let Some(crit) = threshold("crit")? {
+            perfdata = perfdata.with_crit(crit);
+        }
+        if let Some(min) = number("min")? {
+            perfdata = perfdata.with_min(min);
+        }
+        if let Some(max) = number("max")? {
+            perfdata = perfdata.with_max(max);
+        }
+
+        let timestamp = timestamp
+            .map(|t| {
+                t.parse()
+                    .map_err(|_| InfluxParseError::InvalidTimestamp(t.to_string()))
+            })
+            .transpose()?;
+
+        Ok(InfluxPoint {
+            measurement,
+            host: require(TAG_HOST)?,
+            service: tag(TAG_SERVICE),
+            timestamp,
+            perfdata,
+        })
+    }
+}
+
+/// `InfluxExporter` turns the [PerfdataSet](`crate::PerfdataSet`) of a [PerfdataRecord] into
+/// [InfluxPoint]s, one for each [Perfdata].
+///
+/// The measurement is the check command of the record, like in the InfluxdbWriter of Icinga2, or
+/// `perfdata` if the record has none. A fixed measurement can be configured instead.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InfluxExporter {
+    measurement: Option<String>,
+}
+
+impl InfluxExporter {
+    /// Create a new `InfluxExporter`, using the check command as measurement
+    pub fn new() -> Self {
+        InfluxExporter::default()
+    }
+
+    /// Use the given measurement for all points
+    #[must_use]
+    pub fn with_measurement<T: Into<String>>(mut self, measurement: T) -> Self {
+        self.measurement = Some(measurement.into());
+        self
+    }
+
+    /// Returns the points for all perfdata of the record. Points have no timestamp if the
+    /// timestamp of the record can't be represented in nanoseconds.
+    pub fn points(&self, record: &PerfdataRecord) -> Vec<InfluxPoint> {
+        let measurement = self
+            .measurement
+            .as_deref()
+            .or(record.check_command())
+            .unwrap_or(DEFAULT_MEASUREMENT);
+        let service = match record.kind() {
+            RecordKind::Host => None,
+            RecordKind::Service => record.service_description().map(str::to_string),
+        };
+        let timestamp = record.timestamp().checked_mul(NANOS_PER_SECOND);
+
+        record
+            .perfdata()
+            .data()
+            .map(|pd| InfluxPoint {
+                measurement: measurement.to_string(),
+                host: record.host_name().to_string(),
+                service: service.clone(),
+                timestamp,
+                perfdata: pd.clone().into_owned(),
+            })
+            .collect()
+    }
+
+    /// Writes the points for all perfdata of the record as lines, skipping points without any
+    /// field, as those are invalid in the line protocol
+    pub fn write<W: Write>(&self, writer: &mut W, record: &PerfdataRecord) -> std::io::Result<()> {
+        for point in self.points(record) {
+            if !point.fields().is_empty() {
+                writeln!(writer, "{}", point)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn escape<'a>(s: &'a str, special: &[char]) -> Cow<'a, str> {
+    if !s.contains(special) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len() + 4);
+    for c in s.chars() {
+        if special.contains(&c) {
+            escaped.push(ESCAPE);
+        }
+        escaped.push(c);
+    }
+    Cow::Owned(escaped)
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == ESCAPE && KEY_SPECIAL.contains(next) => {
+                unescaped.push(*next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+// Splits at each delimiter which is not escaped, and optionally not within a quoted string
+fn split_unescaped(s: &str, delimiter: char, quotes: bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    let mut quoted = false;
+    for (idx, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            ESCAPE => escaped = true,
+            '"' if quotes => quoted = !quoted,
+            _ if c == delimiter && !quoted => {
+                parts.push(&s[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn split_pair(s: &str) -> Option<(&str, &str)> {
+    match split_unescaped(s, '=', false).as_slice() {
+        [key, value] if !key.is_empty() => Some((key, value)),
+        _ => None,
+    }
+}
+
+fn parse_number(s: &str) -> Result<f64, InfluxParseError> {
+    s.strip_suffix(['i', 'u'])
+        .unwrap_or(s)
+        .parse()
+        .map_err(|_| InfluxParseError::InvalidField(s.to_string()))
+}
+
+fn parse_bool(s: &str) -> Result<bool, InfluxParseError> {
+    match s {
+        "t" | "T" | "true" | "True" | "TRUE" => Ok(true),
+        "f" | "F" | "false" | "False" | "FALSE" => Ok(false),
+        _ => Err(InfluxParseError::InvalidField(s.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::InvalidUnitError;
+    use crate::PerfdataSet;
+
+    fn record() -> PerfdataRecord<'static> {
+        let pds: PerfdataSet = vec![
+            Perfdata::megabytes("/var/log, old", 818)
+                .with_warn(ThresholdRange::above_pos(970))
+                .with_crit(ThresholdRange::inside(10, 975))
+                .with_min(0),
+            Perfdata::unitless("a=b", 1).with_warn(ThresholdRange::above(5)),
+            Perfdata::undetermined("unknown"),
+        ]
+        .into();
+        PerfdataRecord::service(1700000000, "web01", "disk space")
+            .with_check_command("disk")
+            .with_perfdata(pds)
+    }
+
+    #[test]
+    fn test_format_points() {
+        let lines: Vec<String> = InfluxExporter::new()
+            .points(&record())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(
+            lines,
+            [
+                "disk,hostname=web01,metric=/var/log\\,\\ old,service=disk\\ space,unit=MB value=818,warn=970,crit_min=10,crit_max=975,min=0,crit_inside=true 1700000000000000000",
+                "disk,hostname=web01,metric=a\\=b,service=disk\\ space value=1,warn_max=5 1700000000000000000",
+                "disk,hostname=web01,metric=unknown,service=disk\\ space 1700000000000000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_skips_points_without_fields() {
+        let mut written = Vec::new();
+        InfluxExporter::new()
+            .with_measurement("nagios perf")
+            .write(&mut written, &record())
+            .unwrap();
+        let written = String::from_utf8(written).unwrap();
+
+        assert_eq!(written.lines().count(), 2);
+        assert!(written.starts_with("nagios\\ perf,hostname=web01,"));
+    }
+
+    #[test]
+    fn test_parse_points() {
+        let record = record();
+        let points = InfluxExporter::new().points(&record);
+
+        for (point, pd) in points.iter().zip(record.perfdata().data()).take(2) {
+            let parsed: InfluxPoint = point.to_string().parse().unwrap();
+
+            assert_eq!(&parsed, point);
+            assert_eq!(parsed.perfdata(), pd);
+        }
+    }
+
+    #[test]
+    fn test_parse_special_characters() {
+        let pds: PerfdataSet = vec![
+            Perfdata::gigabytes("C:\\ used", 20.5).with_max(50),
+            Perfdata::unitless("trailing\\", 1),
+            Perfdata::unitless("say \"hi\"", 2),
+        ]
+        .into();
+        let record = PerfdataRecord::service(1700000000, "win01", "C:\\ Drive").with_perfdata(pds);
+        let points = InfluxExporter::new().points(&record);
+
+        assert!(points[0]
+            .to_string()
+            .starts_with(r"perfdata,hostname=win01,metric=C:\\\ used,service=C:\\\ Drive,"));
+        for (point, pd) in points.iter().zip(record.perfdata().data()) {
+            let parsed: InfluxPoint = point.to_string().parse().unwrap();
+
+            assert_eq!(&parsed, point);
+            assert_eq!(parsed.perfdata(), pd);
+        }
+    }
+
+    #[test]
+    fn test_timestamp_overflow() {
+        let record = PerfdataRecord::host(i64::MAX, "web01")
+            .with_perfdata(vec![Perfdata::unitless("a", 1)].into());
+
+        let points = InfluxExporter::new().points(&record);
+
+        assert_eq!(points[0].timestamp(), None);
+        assert_eq!(
+            points[0].to_string(),
+            "perfdata,hostname=web01,metric=a value=1"
+        );
+    }
+
+    #[test]
+    fn test_parse_foreign_points() {
+        let line =
+            "ping,hostname=gw,metric=rta,unit=ms value=12i,warn_max=100,crit_min=5,crit_inside=t";
+
+        let point: InfluxPoint = line.parse().unwrap();
+
+        assert_eq!(point.measurement(), "ping");
+        assert_eq!(point.service_description(), None);
+        assert_eq!(point.timestamp(), None);
+        assert_eq!(
+            point.into_perfdata(),
+            Perfdata::milliseconds("rta", 12)
+                .with_warn(ThresholdRange::outside(f64::NEG_INFINITY, 100.0))
+                .with_crit(ThresholdRange::inside(5.0, f64::INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "disk,hostname=a".parse::<InfluxPoint>(),
+            Err(InfluxParseError::MissingFields)
+        );
+        assert_eq!(
+            "disk,hostname=a value=1".parse::<InfluxPoint>(),
+            Err(InfluxParseError::MissingTag(TAG_LABEL))
+        );
+        assert_eq!(
+            "disk,metric=a value=x".parse::<InfluxPoint>(),
+            Err(InfluxParseError::InvalidField("x".to_string()))
+        );
+        assert_eq!(
+            "disk,hostname=a,metric=b value=1 later".parse::<InfluxPoint>(),
+            Err(InfluxParseError::InvalidTimestamp("later".to_string()))
+        );
+        assert_eq!(
+            "disk,metric=a,unit=x;y value=1".parse::<InfluxPoint>(),
+            Err(InfluxParseError::InvalidUnit(InvalidUnitError(
+                "x;y".to_string()
+            )))
+        );
+    }
+}
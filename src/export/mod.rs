@@ -0,0 +1,7 @@
+mod graphite;
+mod influx;
+mod prometheus;
+
+pub use graphite::{GraphiteExporter, GraphiteMetric};
+pub use influx::{InfluxExporter, InfluxPoint};
+pub use prometheus::{OpenMetrics, ThresholdStyle};
@@ -0,0 +1,520 @@
+use crate::error::OpenMetricsParseError;
+use crate::{Perfdata, PerfdataSet, Unit};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::Write;
+
+// Source: https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md
+// Each perfdata item is a metric family, named after its label with the base unit as suffix:
+// # TYPE <name>_<unit> gauge
+// # UNIT <name>_<unit> <unit>
+// <name>_<unit> <value>
+// Counters are of type counter, their sample is suffixed with `_total` and they have no unit.
+// Thresholds, minimum and maximum are either separate gauge families, or labels of the sample:
+// # TYPE <name>_<unit>_warn gauge
+// <name>_<unit>_warn{bound="lower"} <start>
+// <name>_<unit>_warn{bound="upper"} <end>
+// <name>_<unit>{warn_lower="<start>",warn_upper="<end>",min="<min>"} <value>
+// Notes:
+//     infinite bounds of thresholds are left out, whether a threshold alerts inside its range is
+//     not exposed. Names only consist of ASCII letters, digits, underscores and colons.
+//     labels which are sanitized into a name that is already taken, e.g. `a.b` after `a_b`, get
+//     a number as suffix before the unit, starting at 2: `a_b_2_seconds`.
+
+const COUNTER_SUFFIX: &str = "_total";
+const EOF: &str = "# EOF";
+
+/// How thresholds, minimum and maximum of [Perfdata] are exposed in OpenMetrics
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ThresholdStyle {
+    /// As separate gauge families, named after the metric with `_warn`, `_crit`, `_min` and
+    /// `_max` as suffix. The bounds of thresholds are distinguished by the label `bound`, which is
+    /// either `lower` or `upper`.
+    #[default]
+    Series,
+    /// As labels of the sample, named `warn_lower`, `warn_upper`, `crit_lower`, `crit_upper`, `min`
+    /// and `max`
+    Labels,
+}
+
+/// `OpenMetrics` converts [PerfdataSet]s into the
+/// [OpenMetrics](https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md)
+/// text format, which is scraped by Prometheus, and parses it back into [Perfdata].
+///
+/// Metric names are sanitized labels, with an optional namespace as prefix. Labels which result in
+/// the same name, like `a.b` and `a_b`, are told apart by a numeric suffix like `a_b_2`. Values are
+/// converted into their base unit (e.g. milliseconds into seconds, kilobytes into bytes), which is
+/// appended to the name. Percentages can be converted into ratios from 0 to 1.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OpenMetrics {
+    namespace: Option<String>,
+    percentage_as_ratio: bool,
+    thresholds: ThresholdStyle,
+}
+
+impl OpenMetrics {
+    /// Create a new `OpenMetrics` converter without namespace, keeping percentages and exposing
+    /// thresholds as separate series
+    pub fn new() -> Self {
+        OpenMetrics::default()
+    }
+
+    /// Prefix all metric names with the namespace, separated by an underscore
+    #[must_use]
+    pub fn with_namespace<T: Into<String>>(mut self, namespace: T) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Expose percentages as ratio from 0 to 1 with the unit `ratio`, instead of with the unit
+    /// `percent`
+    #[must_use]
+    pub fn with_percentage_as_ratio(mut self, ratio: bool) -> Self {
+        self.percentage_as_ratio = ratio;
+        self
+    }
+
+    /// Set how thresholds, minimum and maximum are exposed
+    #[must_use]
+    pub fn with_threshold_style(mut self, style: ThresholdStyle) -> Self {
+        self.thresholds = style;
+        self
+    }
+
+    /// Returns the exposition of all perfdata in the set, terminated by `# EOF`
+    pub fn render(&self, pds: &PerfdataSet) -> String {
+        let mut out = String::new();
+        let mut taken = HashSet::new();
+        for pd in pds.data() {
+            self.render_perfdata(&mut out, &mut taken, pd);
+        }
+        out.push_str(EOF);
+        out.push('\n');
+        out
+    }
+
+    /// Writes the exposition of all perfdata in the set
+    pub fn write<W: Write>(&self, writer: &mut W, pds: &PerfdataSet) -> std::io::Result<()> {
+        writer.write_all(self.render(pds).as_bytes())
+    }
+
+    // Taken are the names of all families and samples written so far
+    fn render_perfdata(&self, out: &mut String, taken: &mut HashSet<String>, pd: &Perfdata) {
+        let ratio = self.percentage_as_ratio && pd.unit() == &Unit::Percentage;
+        let mut pd = pd.clone().into_base_unit();
+        if ratio {
+            pd = pd.map_values(|value| value / 100.0);
+        }
+        let (unit, counter) = match pd.unit() {
+            Unit::Counter => (None, true),
+            Unit::Seconds => (Some("seconds"), false),
+            Unit::Bytes => (Some("bytes"), false),
+            Unit::Percentage if ratio => (Some("ratio"), false),
+            Unit::Percentage => (Some("percent"), false),
+            _ => (None, false),
+        };
+
+        let mut limits = Vec::new();
+        for (key, threshold) in [("warn", pd.warn()), ("crit", pd.crit())] {
+            if let Some(t) = threshold {
+                limits.push((key, "lower", t.start()));
+                limits.push((key, "upper", t.end()));
+            }
+        }
+        limits.push(("min", "", pd.min().unwrap_or(f64::INFINITY)));
+        limits.push(("max", "", pd.max().unwrap_or(f64::INFINITY)));
+        limits.retain(|(_, _, value)| value.is_finite());
+
+        let unit_suffix = unit.map(|unit| format!("_{}", unit)).unwrap_or_default();
+        let label = self.metric_name(pd.label());
+        let base = label.strip_suffix(&unit_suffix).unwrap_or(&label);
+        let mut number = 1;
+        let name = loop {
+            let mut name = base.to_string();
+            if number > 1 {
+                write!(name, "_{}", number).unwrap();
+            }
+            name.push_str(&unit_suffix);
+
+            let mut names = vec![name.clone()];
+            if counter {
+                names.push(format!("{}{}", name, COUNTER_SUFFIX));
+            }
+            if self.thresholds == ThresholdStyle::Series {
+                names.extend(limits.iter().map(|(key, _, _)| format!("{}_{}", name, key)));
+            }
+            if !names.iter().any(|n| taken.contains(n)) {
+                taken.extend(names);
+                break name;
+            }
+            number += 1;
+        };
+
+        let kind = if counter { "counter" } else { "gauge" };
+        writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+        if let Some(unit) = unit {
+            writeln!(out, "# UNIT {} {}", name, unit).unwrap();
+        }
+
+        let sample = if counter {
+            format!("{}{}", name, COUNTER_SUFFIX)
+        } else {
+            name.clone()
+        };
+        let labels = match self.thresholds {
+            ThresholdStyle::Series => Vec::new(),
+            ThresholdStyle::Labels => limits
+                .iter()
+                .map(|(key, bound, value)| match *bound {
+                    "" => (key.to_string(), format_number(*value)),
+                    bound => (format!("{}_{}", key, bound), format_number(*value)),
+                })
+                .collect(),
+        };
+        let value = pd.value().unwrap_or(f64::NAN);
+        writeln!(
+            out,
+            "{}{} {}",
+            sample,
+            format_labels(&labels),
+            format_number(value)
+        )
+        .unwrap();
+
+        if self.thresholds == ThresholdStyle::Series {
+            let mut family = "";
+            for (key, bound, value) in limits {
+                if family != key {
+                    family = key;
+                    writeln!(out, "# TYPE {}_{} gauge", name, key).unwrap();
+                }
+                let labels = match bound {
+                    "" => Vec::new(),
+                    bound => vec![("bound".to_string(), bound.to_string())],
+                };
+                let labels = format_labels(&labels);
+                writeln!(out, "{}_{}{} {}", name, key, labels, format_number(value)).unwrap();
+            }
+        }
+    }
+
+    fn metric_name(&self, label: &str) -> String {
+        let mut name = String::new();
+        if let Some(namespace) = &self.namespace {
+            name.push_str(&sanitize(namespace));
+            name.push('_');
+        }
+        name.push_str(&sanitize(label));
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+        name
+    }
+
+    /// Parses OpenMetrics or Prometheus text into [Perfdata], one for each sample.
+    ///
+    /// The label of each [Perfdata] is the name of the sample together with its labels as
+    /// written, e.g. `http_requests_total{code="200"}`. Samples of counters get the
+    /// [Counter](`Unit::Counter`) unit, samples with the unit seconds, bytes or percent
+    /// are mapped to the respective [Unit]. Timestamps are ignored, and `NaN` values are
+    /// undetermined.
+    pub fn parse(&self, text: &str) -> Result<PerfdataSet<'static>, OpenMetricsParseError> {
+        let mut types = HashMap::new();
+        let mut units = HashMap::new();
+        let mut pds = PerfdataSet::new();
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                let mut words = comment.split_whitespace();
+                match (words.next(), words.next(), words.next()) {
+                    (Some("TYPE"), Some(name), Some(kind)) => types.insert(name, kind),
+                    (Some("UNIT"), Some(name), Some(unit)) => units.insert(name, unit),
+                    _ => None,
+                };
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || OpenMetricsParseError::InvalidSample {
+                line: idx + 1,
+                sample: line.to_string(),
+            };
+            let (series, name, rest) = split_sample(line).ok_or_else(invalid)?;
+            let value = rest.split_whitespace().next().ok_or_else(invalid)?;
+            let value: f64 = value
+                .parse()
+                .map_err(|_| OpenMetricsParseError::InvalidValue {
+                    line: idx + 1,
+                    value: value.to_string(),
+                })?;
+
+            let family = name.strip_suffix(COUNTER_SUFFIX).unwrap_or(name);
+            let kind = types.get(family).or_else(|| types.get(name));
+            let unit = units.get(family).or_else(|| units.get(name));
+            let unit = match (kind, unit) {
+                (Some(&"counter"), _) => Unit::Counter,
+                (_, Some(&"seconds")) => Unit::Seconds,
+                (_, Some(&"bytes")) => Unit::Bytes,
+                (_, Some(&"percent")) => Unit::Percentage,
+                (_, Some(_)) => Unit::None,
+                (_, None) if name.ends_with("_seconds") => Unit::Seconds,
+                (_, None) if name.ends_with("_bytes") => Unit::Bytes,
+                (_, None) if name.ends_with("_percent") => Unit::Percentage,
+                _ => Unit::None,
+            };
+
+            let label = series.to_string();
+            let pd = if value.is_nan() {
+                Perfdata::undetermined(label)
+            } else {
+                Perfdata::new(label.into(), Some(value), unit)
+            };
+            pds.add(pd);
+        }
+
+        Ok(pds)
+    }
+}
+
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn format_number(value: f64) -> String {
+    match value {
+        v if v.is_nan() => "NaN".to_string(),
+        v if v == f64::INFINITY => "+Inf".to_string(),
+        v if v == f64::NEG_INFINITY => "-Inf".to_string(),
+        v => v.to_string(),
+    }
+}
+
+fn format_labels(labels: &[(String, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+// Splits a sample into the series (name and labels), the name and the remainder
+fn split_sample(line: &str) -> Option<(&str, &str, &str)> {
+    let name_end = line.find(|c: char| c == '{' || c.is_ascii_whitespace())?;
+    let name = &line[..name_end];
+    if name.is_empty() || sanitize(name) != name {
+        return None;
+    }
+
+    let series_end = if line[name_end..].starts_with('{') {
+        let mut quoted = false;
+        let mut escaped = false;
+        let mut end = None;
+        for (idx, c) in line.char_indices().skip(name_end) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quoted = !quoted,
+                '}' if !quoted => {
+                    end = Some(idx + 1);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        end?
+    } else {
+        name_end
+    };
+
+    Some((&line[..series_end], name, &line[series_end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ThresholdRange;
+
+    fn perfdata() -> PerfdataSet<'static> {
+        vec![
+            Perfdata::milliseconds("rta", 12)
+                .with_warn(ThresholdRange::above_pos(100))
+                .with_crit(ThresholdRange::inside(200, 500))
+                .with_min(0),
+            Perfdata::percentage("disk /var", 42).with_max(100),
+            Perfdata::counter("2xx responses", 1234),
+            Perfdata::undetermined("temperature"),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn test_render_series() {
+        let rendered = OpenMetrics::new()
+            .with_namespace("nagios")
+            .render(&perfdata());
+
+        assert_eq!(
+            rendered,
+            r#"# TYPE nagios_rta_seconds gauge
+# UNIT nagios_rta_seconds seconds
+nagios_rta_seconds 0.012
+# TYPE nagios_rta_seconds_warn gauge
+nagios_rta_seconds_warn{bound="lower"} 0
+nagios_rta_seconds_warn{bound="upper"} 0.1
+# TYPE nagios_rta_seconds_crit gauge
+nagios_rta_seconds_crit{bound="lower"} 0.2
+nagios_rta_seconds_crit{bound="upper"} 0.5
+# TYPE nagios_rta_seconds_min gauge
+nagios_rta_seconds_min 0
+# TYPE nagios_disk__var_percent gauge
+# UNIT nagios_disk__var_percent percent
+nagios_disk__var_percent 42
+# TYPE nagios_disk__var_percent_max gauge
+nagios_disk__var_percent_max 100
+# TYPE nagios_2xx_responses counter
+nagios_2xx_responses_total 1234
+# TYPE nagios_temperature gauge
+nagios_temperature NaN
+# EOF
+"#
+        );
+    }
+
+    #[test]
+    fn test_render_labels() {
+        let pds: PerfdataSet = vec![
+            Perfdata::kilobytes("used", 2)
+                .with_warn(ThresholdRange::above(3))
+                .with_max(4),
+            Perfdata::percentage("load_ratio", 50).with_crit(ThresholdRange::below(10)),
+            Perfdata::counter("5xx", 1),
+        ]
+        .into();
+
+        let rendered = OpenMetrics::new()
+            .with_percentage_as_ratio(true)
+            .with_threshold_style(ThresholdStyle::Labels)
+            .render(&pds);
+
+        assert_eq!(
+            rendered,
+            r#"# TYPE used_bytes gauge
+# UNIT used_bytes bytes
+used_bytes{warn_upper="3000",max="4000"} 2000
+# TYPE load_ratio gauge
+# UNIT load_ratio ratio
+load_ratio{crit_lower="0.1"} 0.5
+# TYPE _5xx counter
+_5xx_total 1
+# EOF
+"#
+        );
+    }
+
+    #[test]
+    fn test_render_collisions() {
+        let pds: PerfdataSet = vec![
+            Perfdata::seconds("a.b", 1).with_max(2),
+            Perfdata::seconds("a_b", 3),
+            Perfdata::seconds("a b_max", 4),
+            Perfdata::counter("x", 5),
+            Perfdata::unitless("x_total", 6),
+        ]
+        .into();
+
+        let rendered = OpenMetrics::new().render(&pds);
+
+        assert_eq!(
+            rendered,
+            r#"# TYPE a_b_seconds gauge
+# UNIT a_b_seconds seconds
+a_b_seconds 1
+# TYPE a_b_seconds_max gauge
+a_b_seconds_max 2
+# TYPE a_b_2_seconds gauge
+# UNIT a_b_2_seconds seconds
+a_b_2_seconds 3
+# TYPE a_b_max_seconds gauge
+# UNIT a_b_max_seconds seconds
+a_b_max_seconds 4
+# TYPE x counter
+x_total 5
+# TYPE x_total_2 gauge
+x_total_2 6
+# EOF
+"#
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = r#"# HELP http_requests_total The total number of requests
+# TYPE http_requests counter
+http_requests_total{code="200",path="/a b"} 1027 1395066363000
+http_requests_total{code="400"} 3
+# TYPE process_start_time_seconds gauge
+process_start_time_seconds 1.7e9
+# TYPE memory gauge
+# UNIT memory bytes
+memory 1024
+queue_fill_percent NaN
+"#;
+
+        let pds = OpenMetrics::new().parse(text).unwrap();
+
+        assert_eq!(
+            pds,
+            vec![
+                Perfdata::counter(r#"http_requests_total{code="200",path="/a b"}"#, 1027),
+                Perfdata::counter(r#"http_requests_total{code="400"}"#, 3),
+                Perfdata::seconds("process_start_time_seconds", 1.7e9),
+                Perfdata::bytes("memory", 1024),
+                Perfdata::undetermined("queue_fill_percent"),
+            ]
+            .into()
+        );
+        assert_eq!(
+            PerfdataSet::try_from(pds.to_string().as_str())
+                .unwrap()
+                .into_owned(),
+            pds
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let metrics = OpenMetrics::new();
+
+        assert_eq!(
+            metrics.parse("ok 1\nbroken{a=\"b\" 1"),
+            Err(OpenMetricsParseError::InvalidSample {
+                line: 2,
+                sample: "broken{a=\"b\" 1".to_string()
+            })
+        );
+        assert_eq!(
+            metrics.parse("# TYPE a gauge\na one"),
+            Err(OpenMetricsParseError::InvalidValue {
+                line: 2,
+                value: "one".to_string()
+            })
+        );
+    }
+}
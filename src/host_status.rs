@@ -0,0 +1,240 @@
+use crate::error::ParseHostStatusError;
+use crate::MonitoringStatus;
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::process::ExitStatus;
+use std::str::FromStr;
+
+/// Host Status representing the state of a host, as opposed to the [MonitoringStatus] of a
+/// service.
+///
+/// Host checks report a [MonitoringStatus] by their exit code like service checks, which is mapped
+/// to a `HostStatus` by a [HostStatusPolicy]. [Unreachable](`HostStatus::Unreachable`) is usually
+/// determined by the monitoring engine from the state of parent hosts, not by the check itself.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum HostStatus {
+    /// The host is reachable and working
+    Up,
+    /// The host is down
+    Down,
+    /// The host can't be reached, because a host between it and the monitoring engine is down
+    Unreachable,
+}
+
+impl HostStatus {
+    /// Each status maps to an exit code which can be used by host checks
+    /// Up -> 0
+    /// Down -> 2
+    /// Unreachable -> 3
+    pub fn exit_code(&self) -> i32 {
+        MonitoringStatus::from(*self).exit_code()
+    }
+}
+
+impl Display for HostStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let word = match self {
+            HostStatus::Up => "UP",
+            HostStatus::Down => "DOWN",
+            HostStatus::Unreachable => "UNREACHABLE",
+        };
+        f.write_str(word)
+    }
+}
+
+/// Parses the name of a status regardless of case, e.g. `UP`, `down` or `Unreachable`, and the
+/// abbreviation `UNREACH`
+impl FromStr for HostStatus {
+    type Err = ParseHostStatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "UP" => Ok(HostStatus::Up),
+            "DOWN" => Ok(HostStatus::Down),
+            "UNREACHABLE" | "UNREACH" => Ok(HostStatus::Unreachable),
+            _ => Err(ParseHostStatusError(s.to_string())),
+        }
+    }
+}
+
+/// Maps the status like the [default](`HostStatusPolicy::default()`) [HostStatusPolicy]
+impl From<MonitoringStatus> for HostStatus {
+    fn from(status: MonitoringStatus) -> Self {
+        HostStatusPolicy::default().map(status)
+    }
+}
+
+/// Maps the exit code like the [default](`HostStatusPolicy::default()`) [HostStatusPolicy]
+impl From<i32> for HostStatus {
+    fn from(code: i32) -> Self {
+        HostStatus::from(MonitoringStatus::from(code))
+    }
+}
+
+/// Maps the exit status like the [default](`HostStatusPolicy::default()`) [HostStatusPolicy]
+impl From<ExitStatus> for HostStatus {
+    fn from(status: ExitStatus) -> Self {
+        HostStatus::from(MonitoringStatus::from(status))
+    }
+}
+
+/// Up -> OK, Down -> Critical, Unreachable -> Unknown
+impl From<HostStatus> for MonitoringStatus {
+    fn from(status: HostStatus) -> Self {
+        match status {
+            HostStatus::Up => MonitoringStatus::OK,
+            HostStatus::Down => MonitoringStatus::Critical,
+            HostStatus::Unreachable => MonitoringStatus::Unknown,
+        }
+    }
+}
+
+/// [HostStatus] is serialized by its name, e.g. `UP`
+#[cfg(feature = "serde")]
+impl Serialize for HostStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// [HostStatus] is deserialized from its name like with `from_str()`
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for HostStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+/// `HostStatusPolicy` maps the [MonitoringStatus] reported by a host check to a [HostStatus].
+///
+/// [OK](`MonitoringStatus::OK`) is always [Up](`HostStatus::Up`) and
+/// [Critical](`MonitoringStatus::Critical`) always [Down](`HostStatus::Down`). By default
+/// [Warning](`MonitoringStatus::Warning`) is [Up](`HostStatus::Up`) and
+/// [Unknown](`MonitoringStatus::Unknown`) is [Down](`HostStatus::Down`), like in Nagios and
+/// Icinga.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HostStatusPolicy {
+    warning: HostStatus,
+    unknown: HostStatus,
+}
+
+impl Default for HostStatusPolicy {
+    fn default() -> Self {
+        HostStatusPolicy {
+            warning: HostStatus::Up,
+            unknown: HostStatus::Down,
+        }
+    }
+}
+
+impl HostStatusPolicy {
+    /// Create the default `HostStatusPolicy`
+    pub fn new() -> Self {
+        HostStatusPolicy::default()
+    }
+
+    /// Create a `HostStatusPolicy` which maps [Warning](`MonitoringStatus::Warning`) to
+    /// [Down](`HostStatus::Down`), like the `use_aggressive_host_checking` option of Nagios
+    pub fn aggressive() -> Self {
+        HostStatusPolicy::default().with_warning(HostStatus::Down)
+    }
+
+    /// Set the [HostStatus] for [Warning](`MonitoringStatus::Warning`)
+    #[must_use]
+    pub fn with_warning(mut self, status: HostStatus) -> Self {
+        self.warning = status;
+        self
+    }
+
+    /// Set the [HostStatus] for [Unknown](`MonitoringStatus::Unknown`)
+    #[must_use]
+    pub fn with_unknown(mut self, status: HostStatus) -> Self {
+        self.unknown = status;
+        self
+    }
+
+    /// Maps the status of a host check to a [HostStatus]
+    pub fn map(&self, status: MonitoringStatus) -> HostStatus {
+        match status {
+            MonitoringStatus::OK => HostStatus::Up,
+            MonitoringStatus::Warning => self.warning,
+            MonitoringStatus::Critical => HostStatus::Down,
+            MonitoringStatus::Unknown => self.unknown,
+        }
+    }
+
+    /// Maps the exit code of a host check to a [HostStatus]
+    pub fn map_exit_code(&self, code: i32) -> HostStatus {
+        self.map(MonitoringStatus::from(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        let default = HostStatusPolicy::new();
+        let aggressive = HostStatusPolicy::aggressive();
+        let unreachable = HostStatusPolicy::new().with_unknown(HostStatus::Unreachable);
+
+        let codes = 0..=4;
+        let mapped = |policy: HostStatusPolicy| -> Vec<HostStatus> {
+            codes.clone().map(|c| policy.map_exit_code(c)).collect()
+        };
+
+        assert_eq!(
+            mapped(default),
+            [
+                HostStatus::Up,
+                HostStatus::Up,
+                HostStatus::Down,
+                HostStatus::Down,
+                HostStatus::Down
+            ]
+        );
+        assert_eq!(mapped(aggressive)[1], HostStatus::Down);
+        assert_eq!(mapped(unreachable)[3], HostStatus::Unreachable);
+        assert_eq!(HostStatus::from(1), HostStatus::Up);
+        assert_eq!(
+            HostStatus::from(MonitoringStatus::Unknown),
+            HostStatus::Down
+        );
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(HostStatus::Up.exit_code(), 0);
+        assert_eq!(HostStatus::Down.exit_code(), 2);
+        assert_eq!(HostStatus::Unreachable.exit_code(), 3);
+    }
+
+    #[test]
+    fn test_display_and_parse() {
+        for status in [HostStatus::Up, HostStatus::Down, HostStatus::Unreachable] {
+            assert_eq!(status.to_string().parse(), Ok(status));
+        }
+        assert_eq!("down".parse(), Ok(HostStatus::Down));
+        assert_eq!("Unreach".parse(), Ok(HostStatus::Unreachable));
+        assert_eq!(
+            "OK".parse::<HostStatus>().unwrap_err().to_string(),
+            "unknown host status `OK`"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_string(&HostStatus::Unreachable).unwrap();
+
+        assert_eq!(json, r#""UNREACHABLE""#);
+        assert_eq!(
+            serde_json::from_str::<HostStatus>(r#""up""#).unwrap(),
+            HostStatus::Up
+        );
+        assert!(serde_json::from_str::<HostStatus>(r#""gone""#).is_err());
+    }
+}
@@ -7,14 +7,43 @@
 //!
 //! Parsing and output is implemented to the [Nagios Reference](https://nagios-plugins.org/doc/guidelines.html#AEN200).
 
+mod aggregation;
+mod check_result;
 mod error;
+mod export;
+mod host_status;
+mod lint;
 mod monitoring_status;
 mod perf;
+mod plugin_output;
+mod reader;
+#[cfg(feature = "serde")]
+pub mod serde_as;
+mod spool;
 mod thresholds;
 
+pub use aggregation::{Aggregation, StatusPolicy, UndeterminedPolicy};
+pub use check_result::CheckResult;
+pub use error::{
+    InfluxParseError, InvalidUnitError, OpenMetricsParseError, ParseHostStatusError,
+    ParseMonitoringStatusError, PerfdataField, PerfdataParseError, PerfdataParseErrorKind,
+    PerfdataReadError, PerfdataRecordError, PerfdataViolation, StatusOrderError,
+    UnitConversionError,
+};
+pub use export::{
+    GraphiteExporter, GraphiteMetric, InfluxExporter, InfluxPoint, OpenMetrics, ThresholdStyle,
+};
+pub use host_status::{HostStatus, HostStatusPolicy};
+pub use lint::{Lint, LintCode, Linter};
 pub use monitoring_status::MonitoringStatus;
+pub use perf::ParseOptions;
 pub use perf::Perfdata;
 pub use perf::PerfdataSet;
+pub use perf::Unit;
+pub use perf::{BytePrefixes, Formatted, Humanized, NumberFormat};
+pub use plugin_output::PluginOutput;
+pub use reader::{PerfdataItems, PerfdataReader};
+pub use spool::{PerfdataRecord, PerfdataRecordReader, PerfdataRecordWriter, RecordKind};
 pub use thresholds::ThresholdRange;
 
 #[test]
@@ -0,0 +1,550 @@
+use crate::perf::tokenizer::{find_label_delimiter, tokenize};
+use crate::{Perfdata, PerfdataField};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+// Source: https://nagios-plugins.org/doc/guidelines.html#AEN33 and
+//     https://nagios-plugins.org/doc/guidelines.html#AEN200
+// Notes:
+//     the output is split into text and perfdata like PluginOutput does, but instead of failing
+//     on the first error, all deviations from the guidelines are reported. Units of measurement
+//     are checked on the raw datapoints, as the parser rejects them in thresholds and limits,
+//     and removed before the datapoints are parsed. The unit is what follows the number.
+//     Labels should be unique in their first 19 characters, which is a limitation of RRD.
+
+const PERFDATA_SEPARATOR: char = '|';
+const DATA_DELIMITER: char = ';';
+const RANGE_DELIMITER: char = ':';
+const RRD_LABEL_LENGTH: usize = 19;
+
+/// The code of a [Lint], identifying what is wrong with the plugin output
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LintCode {
+    /// Perfdata which can't be parsed
+    ParseError,
+    /// Perfdata which can be parsed, but is not valid (see
+    /// [Perfdata::validate()](`crate::Perfdata::validate()`))
+    InvalidPerfdata,
+    /// Min or max with a unit of measurement, which may only follow the value
+    UnitOnLimit,
+    /// Warn or crit with the unit of measurement of the value, which may only follow the value
+    UnitOnThreshold,
+    /// Warn or crit with a unit of measurement which differs from the one of the value
+    InconsistentUnit,
+    /// A label which is longer than 19 characters, which is truncated by RRD
+    LabelTooLong,
+    /// A label which is used more than once, or is the same as another one in its first 19
+    /// characters
+    DuplicateLabel,
+    /// Whitespace at the end of a line
+    TrailingWhitespace,
+    /// A `|` in the text, which is taken as separator of the perfdata
+    SeparatorInText,
+}
+
+impl Display for LintCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let word = match self {
+            LintCode::ParseError => "parse-error",
+            LintCode::InvalidPerfdata => "invalid-perfdata",
+            LintCode::UnitOnLimit => "unit-on-limit",
+            LintCode::UnitOnThreshold => "unit-on-threshold",
+            LintCode::InconsistentUnit => "inconsistent-unit",
+            LintCode::LabelTooLong => "label-too-long",
+            LintCode::DuplicateLabel => "duplicate-label",
+            LintCode::TrailingWhitespace => "trailing-whitespace",
+            LintCode::SeparatorInText => "separator-in-text",
+        };
+        f.write_str(word)
+    }
+}
+
+/// A `Lint` is a deviation of plugin output from the
+/// [Nagios Plugin Development Guidelines](https://nagios-plugins.org/doc/guidelines.html),
+/// found by a [Linter]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    code: LintCode,
+    line: usize,
+    span: Range<usize>,
+    message: String,
+}
+
+impl Lint {
+    /// The [LintCode] identifying the kind of the `Lint`
+    pub fn code(&self) -> LintCode {
+        self.code
+    }
+
+    /// The line of the plugin output, counted from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Byte offsets of the offending part in the plugin output
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Description of the `Lint`
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on line {} at {}..{}: {}",
+            self.code, self.line, self.span.start, self.span.end, self.message
+        )
+    }
+}
+
+/// `Linter` checks raw plugin output against the
+/// [Nagios Plugin Development Guidelines](https://nagios-plugins.org/doc/guidelines.html), and
+/// reports all [Lint]s found. It is meant to be used by plugin authors, e.g. in the tests of a
+/// check with [assert_clean()](`Self::assert_clean()`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Linter {
+    allowed: Vec<LintCode>,
+    max_label_length: usize,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter {
+            allowed: Vec::new(),
+            max_label_length: RRD_LABEL_LENGTH,
+        }
+    }
+}
+
+impl Linter {
+    /// Create a new `Linter` reporting all [LintCode]s
+    pub fn new() -> Self {
+        Linter::default()
+    }
+
+    /// Don't report [Lint]s with the given [LintCode]
+    #[must_use]
+    pub fn with_allowed(mut self, code: LintCode) -> Self {
+        self.allowed.push(code);
+        self
+    }
+
+    /// Set the number of characters in which labels must be unique, 19 by default
+    #[must_use]
+    pub fn with_max_label_length(mut self, length: usize) -> Self {
+        self.max_label_length = length;
+        self
+    }
+
+    /// Checks the plugin output, and returns all [Lint]s in the order of their position
+    pub fn lint(&self, output: &str) -> Vec<Lint> {
+        let mut lints = Lints {
+            allowed: &self.allowed,
+            line: 0,
+            found: Vec::new(),
+        };
+        let mut labels = Vec::new();
+        let mut perfdata_only = false;
+
+        let mut offset = 0;
+        for (index, raw_line) in output.split_inclusive('\n').enumerate() {
+            let line = raw_line.trim_end_matches(['\n', '\r']);
+            let start = offset;
+            offset += raw_line.len();
+            lints.line = index + 1;
+
+            let trimmed = line.trim_end();
+            if trimmed.len() < line.len() {
+                lints.push(
+                    LintCode::TrailingWhitespace,
+                    start + trimmed.len()..start + line.len(),
+                    "line ends with whitespace".to_string(),
+                );
+            }
+
+            if perfdata_only {
+                self.lint_perfdata(&mut lints, &mut labels, start, line);
+                continue;
+            }
+
+            // The separator on the first line may be followed by long text, after the second one
+            // only perfdata follows
+            let Some(separator) = line.find(PERFDATA_SEPARATOR) else {
+                continue;
+            };
+            perfdata_only = index > 0;
+            let data_start = separator + PERFDATA_SEPARATOR.len_utf8();
+            let data = &line[data_start..];
+            let separator_span = start + separator..start + data_start;
+            let separator_message = "`|` in text is taken as perfdata separator".to_string();
+
+            if let Some(last) = data.rfind(PERFDATA_SEPARATOR) {
+                lints.push(LintCode::SeparatorInText, separator_span, separator_message);
+                let last_start = data_start + last + PERFDATA_SEPARATOR.len_utf8();
+                self.lint_perfdata(
+                    &mut lints,
+                    &mut labels,
+                    start + last_start,
+                    &line[last_start..],
+                );
+            } else if tokenize(data)
+                .next()
+                .is_some_and(|(_, t)| find_label_delimiter(t).is_none())
+            {
+                lints.push(LintCode::SeparatorInText, separator_span, separator_message);
+            } else {
+                self.lint_perfdata(&mut lints, &mut labels, start + data_start, data);
+            }
+        }
+
+        lints.found
+    }
+
+    /// Checks the plugin output, and panics listing all [Lint]s if any are found
+    #[track_caller]
+    pub fn assert_clean(&self, output: &str) {
+        let lints = self.lint(output);
+        if !lints.is_empty() {
+            let list: Vec<String> = lints.iter().map(|lint| format!("  {}", lint)).collect();
+            panic!(
+                "plugin output has {} lint(s):\n{}\noutput:\n{}",
+                lints.len(),
+                list.join("\n"),
+                output
+            );
+        }
+    }
+
+    // The offset is the position of the data in the complete output
+    fn lint_perfdata(
+        &self,
+        lints: &mut Lints,
+        labels: &mut Vec<String>,
+        offset: usize,
+        data: &str,
+    ) {
+        for (start, token) in tokenize(data) {
+            let at = offset + start;
+            let span = at..at + token.len();
+
+            // Units on thresholds and limits would fail parsing, so they are reported, and the
+            // token is parsed without them
+            let units = lint_units(lints, at, token);
+            let stripped = strip_units(token, &units);
+
+            let pd = match Perfdata::try_from(stripped.as_ref()) {
+                Ok(pd) => pd,
+                Err(e) => {
+                    let e_span = e.span();
+                    let message = match e.field() {
+                        Some(field) => format!("{} in {}", e.kind(), field),
+                        None => e.kind().to_string(),
+                    };
+                    lints.push(
+                        LintCode::ParseError,
+                        at + unstripped_position(e_span.start, &units)
+                            ..at + unstripped_position(e_span.end, &units),
+                        message,
+                    );
+                    continue;
+                }
+            };
+
+            let label = pd.label();
+            let label_span = at..at + find_label_delimiter(token).unwrap_or_default();
+            if label.chars().count() > self.max_label_length {
+                lints.push(
+                    LintCode::LabelTooLong,
+                    label_span.clone(),
+                    format!(
+                        "label `{}` is longer than {} characters",
+                        label, self.max_label_length
+                    ),
+                );
+            }
+
+            let truncated = |l: &str| l.chars().take(self.max_label_length).collect::<String>();
+            match labels.iter().find(|l| truncated(l) == truncated(label)) {
+                Some(other) if other == label => lints.push(
+                    LintCode::DuplicateLabel,
+                    label_span,
+                    format!("label `{}` is used more than once", label),
+                ),
+                Some(other) => lints.push(
+                    LintCode::DuplicateLabel,
+                    label_span,
+                    format!(
+                        "label `{}` is the same as `{}` in its first {} characters",
+                        label, other, self.max_label_length
+                    ),
+                ),
+                None => {}
+            }
+            labels.push(label.to_string());
+
+            for violation in pd.validate() {
+                lints.push(
+                    LintCode::InvalidPerfdata,
+                    span.clone(),
+                    violation.to_string(),
+                );
+            }
+        }
+    }
+}
+
+// Collects all lints which are not allowed, at the line currently checked
+struct Lints<'l> {
+    allowed: &'l [LintCode],
+    line: usize,
+    found: Vec<Lint>,
+}
+
+impl Lints<'_> {
+    fn push(&mut self, code: LintCode, span: Range<usize>, message: String) {
+        if !self.allowed.contains(&code) {
+            self.found.push(Lint {
+                code,
+                line: self.line,
+                span,
+                message,
+            });
+        }
+    }
+}
+
+// Returns the spans of the units on thresholds and limits, relative to the token
+fn lint_units(lints: &mut Lints, offset: usize, token: &str) -> Vec<Range<usize>> {
+    let mut units = Vec::new();
+    let Some(delimiter) = find_label_delimiter(token) else {
+        return units;
+    };
+    let mut position = delimiter + 1;
+    let mut datapoints = token[position..].split(DATA_DELIMITER);
+
+    let value = datapoints.next().unwrap_or_default();
+    let uom = match value {
+        "U" | "u" => "",
+        _ => split_number(value).1,
+    };
+    position += value.len() + DATA_DELIMITER.len_utf8();
+
+    let fields = [
+        PerfdataField::Warn,
+        PerfdataField::Crit,
+        PerfdataField::Min,
+        PerfdataField::Max,
+    ];
+    for (field, datapoint) in fields.into_iter().zip(datapoints) {
+        // Both ends of a range may have a unit. A remainder with digits is no unit of
+        // measurement, and left to the parser to report
+        let mut part_start = position;
+        for part in datapoint.split(RANGE_DELIMITER) {
+            let (_, unit) = split_number(part.trim_start_matches(['@', '~']));
+            if !unit.is_empty() && !unit.contains(|c: char| c.is_ascii_digit()) {
+                let (code, message) = match field {
+                    PerfdataField::Warn | PerfdataField::Crit if unit != uom => (
+                        LintCode::InconsistentUnit,
+                        format!(
+                            "{} has unit of measurement `{}`, but the value has `{}`",
+                            field, unit, uom
+                        ),
+                    ),
+                    PerfdataField::Warn | PerfdataField::Crit => (
+                        LintCode::UnitOnThreshold,
+                        format!("{} must not have a unit of measurement `{}`", field, unit),
+                    ),
+                    _ => (
+                        LintCode::UnitOnLimit,
+                        format!("{} must not have a unit of measurement `{}`", field, unit),
+                    ),
+                };
+                let start = part_start + part.len() - unit.len();
+                lints.push(code, offset + start..offset + start + unit.len(), message);
+                units.push(start..start + unit.len());
+            }
+            part_start += part.len() + RANGE_DELIMITER.len_utf8();
+        }
+        position += datapoint.len() + DATA_DELIMITER.len_utf8();
+    }
+    units
+}
+
+// Splits off the longest prefix which is a number, like `1e-7` or `inf`, from the unit
+fn split_number(datapoint: &str) -> (&str, &str) {
+    let end = datapoint
+        .char_indices()
+        .map(|(i, c)| i + c.len_utf8())
+        .rev()
+        .find(|&end| datapoint[..end].parse::<f64>().is_ok())
+        .unwrap_or_default();
+    datapoint.split_at(end)
+}
+
+fn strip_units<'t>(token: &'t str, units: &[Range<usize>]) -> Cow<'t, str> {
+    if units.is_empty() {
+        return Cow::Borrowed(token);
+    }
+    let mut stripped = String::with_capacity(token.len());
+    let mut position = 0;
+    for unit in units {
+        stripped.push_str(&token[position..unit.start]);
+        position = unit.end;
+    }
+    stripped.push_str(&token[position..]);
+    Cow::Owned(stripped)
+}
+
+// Maps a position in the stripped token back to the position in the token
+fn unstripped_position(mut position: usize, units: &[Range<usize>]) -> usize {
+    for unit in units {
+        if unit.start > position {
+            break;
+        }
+        position += unit.len();
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(output: &str) -> Vec<LintCode> {
+        Linter::new().lint(output).iter().map(Lint::code).collect()
+    }
+
+    #[test]
+    fn test_clean_output() {
+        let output = "DISK OK - free space: / 3326 MB (56%); | /=2643MB;5948;5958;0;5968\n/ 15272 MB (77%);\n/boot 68 MB (69%); | /boot=68MB;88;93;0;98\n'/home'=69357MB;253404;253409;0;253414";
+
+        Linter::new().assert_clean(output);
+    }
+
+    #[test]
+    fn test_units() {
+        let output = "OK | a=10MB;20MB;30;0MB;100 b=5s;10ms";
+
+        let lints = Linter::new().lint(output);
+
+        assert_eq!(
+            codes(output),
+            [
+                LintCode::UnitOnThreshold,
+                LintCode::UnitOnLimit,
+                LintCode::InconsistentUnit
+            ]
+        );
+        assert_eq!(&output[lints[0].span()], "MB");
+        assert_eq!(&output[lints[2].span()], "ms");
+        assert_eq!(
+            lints[1].to_string(),
+            "unit-on-limit on line 1 at 21..23: min must not have a unit of measurement `MB`"
+        );
+    }
+
+    #[test]
+    fn test_units_are_not_numbers() {
+        assert_eq!(codes("OK | a=1;1e-7"), []);
+        assert_eq!(codes("OK | a=1;inf"), []);
+        // The value may only have digits, but the exponent is no unit on the threshold
+        assert_eq!(codes("OK | a=1e3;2e3"), [LintCode::ParseError]);
+        assert_eq!(codes("OK | a=1s;@5s:1e1s"), [LintCode::UnitOnThreshold; 2]);
+    }
+
+    #[test]
+    fn test_units_are_stripped() {
+        let output = "OK | a=5MB;;;0MB a=1 b=1;;;0MB;1x2";
+
+        let lints = Linter::new().lint(output);
+
+        assert_eq!(
+            codes(output),
+            [
+                LintCode::UnitOnLimit,
+                LintCode::DuplicateLabel,
+                LintCode::UnitOnLimit,
+                LintCode::ParseError
+            ]
+        );
+        assert_eq!(&output[lints[3].span()], "1x2");
+    }
+
+    #[test]
+    fn test_labels() {
+        let output = "OK | this_is_a_very_long_label_1=1 this_is_a_very_long_label_2=2 a=1 a=2";
+
+        let lints = Linter::new().lint(output);
+
+        assert_eq!(
+            codes(output),
+            [
+                LintCode::LabelTooLong,
+                LintCode::LabelTooLong,
+                LintCode::DuplicateLabel,
+                LintCode::DuplicateLabel
+            ]
+        );
+        assert_eq!(&output[lints[2].span()], "this_is_a_very_long_label_2");
+        assert_eq!(lints[3].message(), "label `a` is used more than once");
+        Linter::new()
+            .with_max_label_length(30)
+            .with_allowed(LintCode::DuplicateLabel)
+            .assert_clean(output);
+    }
+
+    #[test]
+    fn test_text() {
+        let trailing = "OK \nlong text\t\n| a=1 ";
+        let separator = "OK - in|out ratio is fine";
+        let separators = "OK | all good | a=1;2;3 b=x";
+
+        let lints = Linter::new().lint(trailing);
+
+        assert_eq!(
+            codes(trailing),
+            [
+                LintCode::TrailingWhitespace,
+                LintCode::TrailingWhitespace,
+                LintCode::TrailingWhitespace
+            ]
+        );
+        assert_eq!(lints[1].line(), 2);
+        assert_eq!(lints[1].span(), 13..14);
+        assert_eq!(codes(separator), [LintCode::SeparatorInText]);
+        assert_eq!(
+            codes(separators),
+            [LintCode::SeparatorInText, LintCode::ParseError]
+        );
+        assert_eq!(&separators[Linter::new().lint(separators)[1].span()], "x");
+    }
+
+    #[test]
+    fn test_invalid_perfdata() {
+        let output = "OK | pct=120%;;;0;100";
+
+        let lints = Linter::new().lint(output);
+
+        assert_eq!(
+            codes(output),
+            [LintCode::InvalidPerfdata, LintCode::InvalidPerfdata]
+        );
+        assert_eq!(&output[lints[0].span()], "pct=120%;;;0;100");
+        assert_eq!(
+            lints[1].message(),
+            "percentage 120 in value is not within 0 and 100"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unit-on-limit on line 1")]
+    fn test_assert_clean() {
+        Linter::new().assert_clean("OK | a=1;;;0s");
+    }
+}
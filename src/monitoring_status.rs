@@ -1,4 +1,11 @@
+use crate::error::ParseMonitoringStatusError;
+#[cfg(feature = "serde")]
+use serde::de::{self, Unexpected, Visitor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{Display, Formatter};
+use std::process::ExitStatus;
+use std::str::FromStr;
 
 /// Monitoring Status representing the Status reported to Monitoring Engines like Nagios, Naemon or
 /// Icinga.
@@ -43,3 +50,182 @@ impl MonitoringStatus {
         }
     }
 }
+
+/// Exit codes are mapped to their status, as described in the
+/// [Nagios Plugin API](https://nagios-plugins.org/doc/guidelines.html#AEN78). Codes out of range
+/// are mapped to [Unknown](`MonitoringStatus::Unknown`), so `try_from()` never fails.
+impl From<i32> for MonitoringStatus {
+    fn from(code: i32) -> Self {
+        match code {
+            0 => MonitoringStatus::OK,
+            1 => MonitoringStatus::Warning,
+            2 => MonitoringStatus::Critical,
+            _ => MonitoringStatus::Unknown,
+        }
+    }
+}
+
+/// The exit status of a plugin maps to its status like its exit code. Plugins which were
+/// terminated by a signal are [Unknown](`MonitoringStatus::Unknown`).
+impl From<ExitStatus> for MonitoringStatus {
+    fn from(status: ExitStatus) -> Self {
+        status
+            .code()
+            .map_or(MonitoringStatus::Unknown, MonitoringStatus::from)
+    }
+}
+
+/// Parses the name of a status regardless of case, e.g. `OK`, `warning` or `Critical`, and the
+/// abbreviations `WARN`, `CRIT`, `UNKN` and `UNK`, as well as the first letters `W`, `C` and `U`.
+impl FromStr for MonitoringStatus {
+    type Err = ParseMonitoringStatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "OK" => Ok(MonitoringStatus::OK),
+            "WARNING" | "WARN" | "W" => Ok(MonitoringStatus::Warning),
+            "CRITICAL" | "CRIT" | "C" => Ok(MonitoringStatus::Critical),
+            "UNKNOWN" | "UNKN" | "UNK" | "U" => Ok(MonitoringStatus::Unknown),
+            _ => Err(ParseMonitoringStatusError(s.to_string())),
+        }
+    }
+}
+
+/// [MonitoringStatus] is serialized by its name, e.g. `Warning`. For serialization as exit code see
+/// [serde_as::exit_code](`crate::serde_as::exit_code`).
+#[cfg(feature = "serde")]
+impl Serialize for MonitoringStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// [MonitoringStatus] is deserialized from its name like with `from_str()`. For deserialization
+/// from exit codes see [serde_as::exit_code](`crate::serde_as::exit_code`).
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for MonitoringStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(StatusVisitor)
+    }
+}
+
+// Accepts names, and exit codes from 0 to 3 for serde_as::exit_code
+#[cfg(feature = "serde")]
+pub(crate) struct StatusVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for StatusVisitor {
+    type Value = MonitoringStatus;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("a monitoring status name or exit code from 0 to 3")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        match v {
+            0 => Ok(MonitoringStatus::OK),
+            1 => Ok(MonitoringStatus::Warning),
+            2 => Ok(MonitoringStatus::Critical),
+            3 => Ok(MonitoringStatus::Unknown),
+            _ => Err(E::invalid_value(Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        let v = i64::try_from(v).map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))?;
+        self.visit_i64(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_exit_code() {
+        let statuses: Vec<MonitoringStatus> = (-1..=4).map(MonitoringStatus::from).collect();
+
+        assert_eq!(
+            statuses,
+            [
+                MonitoringStatus::Unknown,
+                MonitoringStatus::OK,
+                MonitoringStatus::Warning,
+                MonitoringStatus::Critical,
+                MonitoringStatus::Unknown,
+                MonitoringStatus::Unknown,
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_exit_status() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let warning = ExitStatus::from_raw(1 << 8);
+        let killed = ExitStatus::from_raw(9);
+
+        assert_eq!(MonitoringStatus::from(warning), MonitoringStatus::Warning);
+        assert_eq!(MonitoringStatus::from(killed), MonitoringStatus::Unknown);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let parse = |s: &str| s.parse::<MonitoringStatus>();
+
+        assert_eq!(parse("ok"), Ok(MonitoringStatus::OK));
+        assert_eq!(parse("Warning"), Ok(MonitoringStatus::Warning));
+        assert_eq!(parse("WARN"), Ok(MonitoringStatus::Warning));
+        assert_eq!(parse("crit"), Ok(MonitoringStatus::Critical));
+        assert_eq!(parse("C"), Ok(MonitoringStatus::Critical));
+        assert_eq!(parse("UNKNOWN"), Ok(MonitoringStatus::Unknown));
+        assert_eq!(parse("unk"), Ok(MonitoringStatus::Unknown));
+        assert_eq!(
+            parse("fine").unwrap_err().to_string(),
+            "unknown monitoring status `fine`"
+        );
+        for status in [
+            MonitoringStatus::OK,
+            MonitoringStatus::Warning,
+            MonitoringStatus::Critical,
+            MonitoringStatus::Unknown,
+        ] {
+            assert_eq!(parse(&status.to_string()), Ok(status));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let statuses: Vec<MonitoringStatus> =
+            serde_json::from_str(r#"["ok", "WARNING", "crit", "Unknown"]"#).unwrap();
+
+        assert_eq!(
+            statuses,
+            [
+                MonitoringStatus::OK,
+                MonitoringStatus::Warning,
+                MonitoringStatus::Critical,
+                MonitoringStatus::Unknown
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&statuses).unwrap(),
+            r#"["OK","Warning","Critical","Unknown"]"#
+        );
+        assert!(serde_json::from_str::<MonitoringStatus>("2").is_err());
+        assert!(serde_json::from_str::<MonitoringStatus>(r#""fine""#).is_err());
+
+        let bytes = bincode::serialize(&statuses).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Vec<MonitoringStatus>>(&bytes).unwrap(),
+            statuses
+        );
+    }
+}
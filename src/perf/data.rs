@@ -1,8 +1,8 @@
+use crate::error::{InvalidUnitError, PerfdataField, PerfdataViolation, UnitConversionError};
 use crate::monitoring_status::MonitoringStatus;
-use crate::perf::{Unit, Value};
+use crate::perf::{NumberFormat, Unit, Value};
 use crate::thresholds::ThresholdRange;
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 
 /// `Perfdata` is the core data structure of this crate. A `Perfdata` represents a  named metric
@@ -16,10 +16,13 @@ use std::fmt::{Display, Formatter};
 /// Critical and Warning [ThresholdRange] instead delimit the ranges where the monitored objects
 /// are considered [Ok](`MonitoringStatus::OK`), [Critical](`MonitoringStatus::Critical`) or in
 /// [Warning](`MonitoringStatus::Warning`) state.
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Copy, Clone, PartialEq)]
+///
+/// Labels are either borrowed, e.g. from the parsed input, or owned, e.g. when created with
+/// `format!`. [into_owned()](`Self::into_owned()`) detaches a [Perfdata] from its input.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Perfdata<'a> {
-    label: &'a str,
+    label: Cow<'a, str>,
+    value: Option<Value>,
     unit: Unit,
     warn: Option<ThresholdRange>,
     crit: Option<ThresholdRange>,
@@ -28,9 +31,10 @@ pub struct Perfdata<'a> {
 }
 
 impl<'a> Perfdata<'a> {
-    fn new(label: &'a str, unit: Unit) -> Self {
+    pub(crate) fn new(label: Cow<'a, str>, value: Option<Value>, unit: Unit) -> Self {
         Perfdata {
             label,
+            value,
             unit,
             warn: None,
             crit: None,
@@ -39,35 +43,100 @@ impl<'a> Perfdata<'a> {
         }
     }
 
-    /// Create a Perfdata without a unit. This name may be subject to change in the
-    /// near future
-    // TODO find a better name, currently it is kind of the opposite of what it is
-    pub fn unit<T: Into<Value>>(label: &'a str, value: T) -> Self {
-        Self::new(label, Unit::None(value.into()))
+    /// Create a Perfdata without a unit, e.g. a number of users or processes
+    pub fn unitless<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::None)
     }
     /// Create a new Perfdata with percent (%) Unit
-    pub fn percentage<T: Into<Value>>(label: &'a str, value: T) -> Self {
-        Self::new(label, Unit::Percentage(value.into()))
+    pub fn percentage<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Percentage)
     }
 
     /// Create a new Perfdata with seconds (s) Unit
-    pub fn seconds<T: Into<Value>>(label: &'a str, value: T) -> Self {
-        Self::new(label, Unit::Seconds(value.into()))
+    pub fn seconds<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Seconds)
+    }
+
+    /// Create a new Perfdata with milliseconds (ms) Unit
+    pub fn milliseconds<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Milliseconds)
+    }
+
+    /// Create a new Perfdata with microseconds (us) Unit
+    pub fn microseconds<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Microseconds)
+    }
+
+    /// Create a new Perfdata with bytes (B) Unit
+    pub fn bytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Bytes)
+    }
+
+    /// Create a new Perfdata with kilobytes (KB, 1000 bytes) Unit
+    pub fn kilobytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Kilobytes)
+    }
+
+    /// Create a new Perfdata with megabytes (MB, 1000² bytes) Unit
+    pub fn megabytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Megabytes)
+    }
+
+    /// Create a new Perfdata with gigabytes (GB, 1000³ bytes) Unit
+    pub fn gigabytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Gigabytes)
+    }
+
+    /// Create a new Perfdata with terabytes (TB, 1000⁴ bytes) Unit
+    pub fn terabytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Terabytes)
+    }
+
+    /// Create a new Perfdata with kibibytes (KiB, 1024 bytes) Unit
+    pub fn kibibytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Kibibytes)
+    }
+
+    /// Create a new Perfdata with mebibytes (MiB, 1024² bytes) Unit
+    pub fn mebibytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Mebibytes)
+    }
+
+    /// Create a new Perfdata with gibibytes (GiB, 1024³ bytes) Unit
+    pub fn gibibytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Gibibytes)
     }
 
-    /// Create a new Perfdata with butes (b) Unit
-    pub fn bytes<T: Into<Value>>(label: &'a str, value: T) -> Self {
-        Self::new(label, Unit::Bytes(value.into()))
+    /// Create a new Perfdata with tebibytes (TiB, 1024⁴ bytes) Unit
+    pub fn tebibytes<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Tebibytes)
     }
 
     /// Create a new Perfdata as an increasing counter (c)
-    pub fn counter<T: Into<Value>>(label: &'a str, value: T) -> Self {
-        Self::new(label, Unit::Counter(value.into()))
+    pub fn counter<L: Into<Cow<'a, str>>, T: Into<Value>>(label: L, value: T) -> Self {
+        Self::new(label.into(), Some(value.into()), Unit::Counter)
+    }
+
+    /// Create a new Perfdata with an arbitrary unit of measurement (UOM), e.g. `rpm` or `°C`.
+    ///
+    /// Well known UOMs like `ms` or `MB` result in the same [Perfdata] as their dedicated
+    /// constructors. Per the spec the UOM must not contain numbers, semicolons or quotes, as the
+    /// formatted output could not be parsed again, which is checked with [Unit::custom()].
+    pub fn custom<L: Into<Cow<'a, str>>, T: Into<Value>>(
+        label: L,
+        value: T,
+        uom: &str,
+    ) -> Result<Self, InvalidUnitError> {
+        Ok(Self::new(
+            label.into(),
+            Some(value.into()),
+            Unit::custom(uom)?,
+        ))
     }
 
     /// Create a new Perfdata where the value could not be determined
-    pub fn undetermined(label: &'a str) -> Self {
-        Self::new(label, Unit::Undetermined)
+    pub fn undetermined<L: Into<Cow<'a, str>>>(label: L) -> Self {
+        Self::new(label.into(), None, Unit::None)
     }
 
     /// Add a minimum value to the [Perfdata]
@@ -133,49 +202,221 @@ impl<'a> Perfdata<'a> {
         }
     }
 
+    /// Checks the [Perfdata] for violations of the rules for valid perfdata. Returns all
+    /// violations found, which is empty for valid [Perfdata]:
+    /// - value, min and max must be finite numbers, thresholds must not be NaN
+    /// - min must not be greater than max
+    /// - the value must be within min and max
+    /// - percentages must be within 0 and 100
+    /// - the value of a counter must not be negative
+    pub fn validate(&self) -> Vec<PerfdataViolation> {
+        let mut violations = Vec::new();
+        // Checked with `iter().all()`, as `Option::is_none_or()` requires Rust 1.82
+        let finite = |v: Option<Value>| v.iter().all(|v| v.is_finite());
+        let not_nan =
+            |t: Option<ThresholdRange>| t.iter().all(|t| !t.start().is_nan() && !t.end().is_nan());
+
+        for (field, valid) in [
+            (PerfdataField::Value, finite(self.value)),
+            (PerfdataField::Warn, not_nan(self.warn)),
+            (PerfdataField::Crit, not_nan(self.crit)),
+            (PerfdataField::Min, finite(self.min)),
+            (PerfdataField::Max, finite(self.max)),
+        ] {
+            if !valid {
+                violations.push(PerfdataViolation::NotFinite(field));
+            }
+        }
+
+        let value = self.value.filter(|v| v.is_finite());
+        let min = self.min.filter(|v| v.is_finite());
+        let max = self.max.filter(|v| v.is_finite());
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                violations.push(PerfdataViolation::MinAboveMax { min, max });
+            }
+        }
+        if let Some(value) = value {
+            if let Some(min) = min.filter(|min| value < *min) {
+                violations.push(PerfdataViolation::BelowMin { value, min });
+            }
+            if let Some(max) = max.filter(|max| value > *max) {
+                violations.push(PerfdataViolation::AboveMax { value, max });
+            }
+        }
+
+        match self.unit {
+            Unit::Percentage => {
+                for (field, percentage) in [
+                    (PerfdataField::Value, value),
+                    (PerfdataField::Min, min),
+                    (PerfdataField::Max, max),
+                ] {
+                    if let Some(value) = percentage.filter(|p| !(0.0..=100.0).contains(p)) {
+                        violations.push(PerfdataViolation::PercentageOutOfRange { field, value });
+                    }
+                }
+            }
+            Unit::Counter => {
+                if let Some(value) = value.filter(|v| *v < 0.0) {
+                    violations.push(PerfdataViolation::NegativeCounter(value));
+                }
+            }
+            _ => {}
+        }
+
+        violations
+    }
+
     fn has_any_thresholds_or_limits(&self) -> bool {
         self.warn.is_some() || self.crit.is_some() || self.min.is_some() || self.max.is_some()
     }
 
-    /// The given numerical `Value` of the [Perfdata]
+    /// The given numerical `Value` of the [Perfdata], `None` if it could not be determined
     pub fn value(&self) -> Option<Value> {
-        match self.unit {
-            Unit::None(v) => Some(v),
-            Unit::Percentage(v) => Some(v),
-            Unit::Seconds(v) => Some(v),
-            Unit::Bytes(v) => Some(v),
-            Unit::Counter(v) => Some(v),
-            Unit::Undetermined => None,
-        }
+        self.value
+    }
+
+    /// The [Unit] of the [Perfdata]
+    pub fn unit(&self) -> &Unit {
+        &self.unit
+    }
+
+    /// The unit of measurement (UOM) of the [Perfdata] as it appears in its string
+    /// representation, e.g. `ms`, `%` or `rpm`. Empty if there is no UOM.
+    pub fn uom(&self) -> &str {
+        self.unit.uom()
+    }
+
+    /// The warning [ThresholdRange], if defined
+    pub fn warn(&self) -> Option<ThresholdRange> {
+        self.warn
+    }
+
+    /// The critical [ThresholdRange], if defined
+    pub fn crit(&self) -> Option<ThresholdRange> {
+        self.crit
+    }
+
+    /// The minimum value, if defined
+    pub fn min(&self) -> Option<Value> {
+        self.min
+    }
+
+    /// The maximum value, if defined
+    pub fn max(&self) -> Option<Value> {
+        self.max
     }
 
     /// The given `Label` of the [Perfdata]
     pub fn label(&self) -> &str {
-        self.label
+        &self.label
+    }
+
+    /// Converts the [Perfdata] into one which owns its label, and as such is no longer bound to
+    /// the lifetime of the input it was parsed from
+    pub fn into_owned(self) -> Perfdata<'static> {
+        Perfdata {
+            label: Cow::Owned(self.label.into_owned()),
+            value: self.value,
+            unit: self.unit,
+            warn: self.warn,
+            crit: self.crit,
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    /// Converts the value, thresholds and limits into the [base unit](`Unit::base()`), e.g.
+    /// `'rta'=1500ms;1000` into `'rta'=1.5s;1`
+    pub fn into_base_unit(self) -> Self {
+        let unit = self.unit.clone();
+        Perfdata {
+            unit: unit.base(),
+            ..self.map_values(|value| unit.to_base(value))
+        }
+    }
+
+    /// Converts the value, thresholds and limits into the given [Unit], e.g. `'used'=2048KiB;;;0`
+    /// into `'used'=2MiB;;;0`. Byte units with SI prefixes are decimal, so `2048KB` is converted
+    /// into `2.048MB`. Fails if the units are not of the same quantity.
+    pub fn into_unit(self, unit: Unit) -> Result<Self, UnitConversionError> {
+        if self.unit.base() != unit.base() {
+            return Err(UnitConversionError {
+                from: self.unit,
+                to: unit,
+            });
+        }
+        let from = self.unit.clone();
+        let converted = self.map_values(|value| from.rescale(value, &unit));
+        Ok(Perfdata { unit, ..converted })
+    }
+
+    // Applies the function to the value, thresholds and limits
+    pub(crate) fn map_values<F: Fn(Value) -> Value>(self, f: F) -> Self {
+        Perfdata {
+            value: self.value.map(&f),
+            warn: self.warn.map(|t| t.map(&f)),
+            crit: self.crit.map(|t| t.map(&f)),
+            min: self.min.map(&f),
+            max: self.max.map(&f),
+            ..self
+        }
     }
 }
 
-fn fmt_threshold<T: Display>(f: &mut Formatter<'_>, th: Option<T>) -> std::fmt::Result {
-    match th {
-        None => write!(f, ";"),
-        Some(threshold) => write!(f, "{};", threshold),
+// Quote characters in labels are escaped by two single quotes
+fn fmt_label(f: &mut Formatter<'_>, label: &str) -> std::fmt::Result {
+    write!(f, "'")?;
+    for (i, part) in label.split('\'').enumerate() {
+        if i != 0 {
+            write!(f, "''")?;
+        }
+        write!(f, "{}", part)?;
     }
+    write!(f, "'")
 }
 
-impl Display for Perfdata<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "'{}'={};", self.label, self.unit)?;
+impl Perfdata<'_> {
+    pub(crate) fn fmt_with(
+        &self,
+        f: &mut Formatter<'_>,
+        format: &NumberFormat,
+    ) -> std::fmt::Result {
+        fmt_label(f, &self.label)?;
+        match self.value {
+            Some(value) => {
+                write!(f, "=")?;
+                format.fmt_number(f, value, &self.unit)?;
+                write!(f, "{};", self.unit)?;
+            }
+            None => write!(f, "=U;")?,
+        }
 
         if self.has_any_thresholds_or_limits() {
-            fmt_threshold(f, self.warn)?;
-            fmt_threshold(f, self.crit)?;
-            fmt_threshold(f, self.min)?;
-            fmt_threshold(f, self.max)?;
+            for threshold in [self.warn, self.crit] {
+                if let Some(threshold) = threshold {
+                    threshold.fmt_with(f, format, &self.unit)?;
+                }
+                write!(f, ";")?;
+            }
+            for limit in [self.min, self.max] {
+                if let Some(limit) = limit {
+                    format.fmt_number(f, limit, &self.unit)?;
+                }
+                write!(f, ";")?;
+            }
         }
         Ok(())
     }
 }
 
+impl Display for Perfdata<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with(f, &NumberFormat::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,12 +427,22 @@ mod tests {
         let label = "test";
         for unit in Unit::iter() {
             let perfdata = match unit {
-                Unit::None(_) => Perfdata::unit(label, 0),
-                Unit::Percentage(_) => Perfdata::percentage(label, 0.0),
-                Unit::Seconds(_) => Perfdata::seconds(label, 0_u8),
-                Unit::Bytes(_) => Perfdata::bytes(label, 0_u16),
-                Unit::Counter(_) => Perfdata::counter(label, 0.0_f32),
-                Unit::Undetermined => Perfdata::undetermined(label),
+                Unit::None => Perfdata::unitless(label, 0),
+                Unit::Percentage => Perfdata::percentage(label, 0.0),
+                Unit::Seconds => Perfdata::seconds(label, 0_u8),
+                Unit::Milliseconds => Perfdata::milliseconds(label, 0_u8),
+                Unit::Microseconds => Perfdata::microseconds(label, 0_u8),
+                Unit::Bytes => Perfdata::bytes(label, 0_u16),
+                Unit::Kilobytes => Perfdata::kilobytes(label, 0_u16),
+                Unit::Megabytes => Perfdata::megabytes(label, 0_u16),
+                Unit::Gigabytes => Perfdata::gigabytes(label, 0_u16),
+                Unit::Terabytes => Perfdata::terabytes(label, 0_u16),
+                Unit::Kibibytes => Perfdata::kibibytes(label, 0_u16),
+                Unit::Mebibytes => Perfdata::mebibytes(label, 0_u16),
+                Unit::Gibibytes => Perfdata::gibibytes(label, 0_u16),
+                Unit::Tebibytes => Perfdata::tebibytes(label, 0_u16),
+                Unit::Counter => Perfdata::counter(label, 0.0_f32),
+                Unit::Custom(_) => Perfdata::custom(label, 0, "rpm").unwrap(),
             };
 
             let label_got = perfdata.label();
@@ -199,51 +450,92 @@ mod tests {
             if let Some(value) = perfdata.value() {
                 assert_eq!(value, Value::default())
             }
+            if !matches!(unit, Unit::Custom(_)) {
+                assert_eq!(perfdata.unit(), &unit)
+            }
             assert_eq!(label, label_got)
         }
+
+        let undetermined = Perfdata::undetermined(label);
+        assert_eq!(undetermined.value(), None);
+        assert_eq!(undetermined.unit(), &Unit::None);
     }
 
     #[test]
     fn test_format() {
         for unit in Unit::iter() {
             match unit {
-                Unit::None(_) => assert_eq!(Perfdata::unit("unit", 0).to_string(), "'unit'=0;"),
-                Unit::Percentage(_) => {
+                Unit::None => assert_eq!(Perfdata::unitless("unit", 0).to_string(), "'unit'=0;"),
+                Unit::Percentage => {
                     assert_eq!(
                         Perfdata::percentage("percentage", 50).to_string(),
                         "'percentage'=50%;"
                     )
                 }
-                Unit::Seconds(_) => {
+                Unit::Seconds => {
                     assert_eq!(
                         Perfdata::seconds("seconds", 1.234).to_string(),
                         "'seconds'=1.234s;"
                     )
                 }
-                Unit::Bytes(_) => assert_eq!(
+                Unit::Milliseconds => assert_eq!(
+                    Perfdata::milliseconds("ms", 12.5).to_string(),
+                    "'ms'=12.5ms;"
+                ),
+                Unit::Microseconds => {
+                    assert_eq!(Perfdata::microseconds("us", 800).to_string(), "'us'=800us;")
+                }
+                Unit::Bytes => assert_eq!(
                     Perfdata::bytes("bytes", 0.0001).to_string(),
-                    "'bytes'=0.0001b;"
+                    "'bytes'=0.0001B;"
                 ),
-                Unit::Counter(_) => assert_eq!(
+                Unit::Kilobytes => {
+                    assert_eq!(Perfdata::kilobytes("kb", 1).to_string(), "'kb'=1KB;")
+                }
+                Unit::Megabytes => {
+                    assert_eq!(Perfdata::megabytes("mb", 2).to_string(), "'mb'=2MB;")
+                }
+                Unit::Gigabytes => {
+                    assert_eq!(Perfdata::gigabytes("gb", 3).to_string(), "'gb'=3GB;")
+                }
+                Unit::Terabytes => {
+                    assert_eq!(Perfdata::terabytes("tb", 4).to_string(), "'tb'=4TB;")
+                }
+                Unit::Kibibytes => {
+                    assert_eq!(Perfdata::kibibytes("kib", 1).to_string(), "'kib'=1KiB;")
+                }
+                Unit::Mebibytes => {
+                    assert_eq!(Perfdata::mebibytes("mib", 2).to_string(), "'mib'=2MiB;")
+                }
+                Unit::Gibibytes => {
+                    assert_eq!(Perfdata::gibibytes("gib", 3).to_string(), "'gib'=3GiB;")
+                }
+                Unit::Tebibytes => {
+                    assert_eq!(Perfdata::tebibytes("tib", 4).to_string(), "'tib'=4TiB;")
+                }
+                Unit::Counter => assert_eq!(
                     Perfdata::counter("counter", 12345).to_string(),
                     "'counter'=12345c;"
                 ),
-                Unit::Undetermined => {
-                    assert_eq!(
-                        Perfdata::undetermined("undetermined").to_string(),
-                        "'undetermined'=U;"
-                    )
-                }
+                Unit::Custom(_) => assert_eq!(
+                    Perfdata::custom("custom", 21.5, "°C").unwrap().to_string(),
+                    "'custom'=21.5°C;"
+                ),
             };
         }
+
+        assert_eq!(
+            Perfdata::undetermined("undetermined").to_string(),
+            "'undetermined'=U;"
+        )
     }
 
     #[test]
     fn test_format_partial_thresholds() {
-        let just_warn = Perfdata::unit("label", 10).with_warn(ThresholdRange::above_pos(20));
-        let just_crit = Perfdata::unit("label", 10).with_crit(ThresholdRange::above_pos(30));
-        let just_min = Perfdata::unit("label", 10).with_min(0);
-        let just_max = Perfdata::unit("label", 10).with_max(100);
+        let just_warn = Perfdata::unitless("label", 10).with_warn(ThresholdRange::above_pos(20));
+        let just_crit = Perfdata::unitless("label", 10).with_crit(ThresholdRange::above_pos(30));
+        let just_min = Perfdata::unitless("label", 10).with_min(0);
+        let just_max = Perfdata::unitless("label", 10).with_max(100);
 
         let f_warn = just_warn.to_string();
         let f_crit = just_crit.to_string();
@@ -262,18 +554,24 @@ mod tests {
         let crit = ThresholdRange::above_pos(30);
         let min = -50;
         let max = 50;
+        let with_thresholds = |pd: Perfdata<'static>| {
+            pd.with_warn(warn)
+                .with_crit(crit)
+                .with_min(min)
+                .with_max(max)
+        };
 
         for unit in Unit::iter() {
             match unit {
-                Unit::None(_) => {
-                    let unit = Perfdata::unit("unit", 0)
+                Unit::None => {
+                    let unit = Perfdata::unitless("unit", 0)
                         .with_warn(warn)
                         .with_crit(crit)
                         .with_min(min)
                         .with_max(max);
                     assert_eq!(unit.to_string(), "'unit'=0;20;30;-50;50;")
                 }
-                Unit::Percentage(_) => {
+                Unit::Percentage => {
                     let percentage = Perfdata::percentage("percentage", 50)
                         .with_warn(warn)
                         .with_crit(crit)
@@ -281,7 +579,7 @@ mod tests {
                         .with_max(max);
                     assert_eq!(percentage.to_string(), "'percentage'=50%;20;30;-50;50;")
                 }
-                Unit::Seconds(_) => {
+                Unit::Seconds => {
                     let seconds = Perfdata::seconds("seconds", 1.234)
                         .with_warn(warn)
                         .with_crit(crit)
@@ -289,15 +587,55 @@ mod tests {
                         .with_max(max);
                     assert_eq!(seconds.to_string(), "'seconds'=1.234s;20;30;-50;50;")
                 }
-                Unit::Bytes(_) => {
+                Unit::Bytes => {
                     let bytes = Perfdata::bytes("bytes", 0.0001)
                         .with_warn(warn)
                         .with_crit(crit)
                         .with_min(min)
                         .with_max(max);
-                    assert_eq!(bytes.to_string(), "'bytes'=0.0001b;20;30;-50;50;")
+                    assert_eq!(bytes.to_string(), "'bytes'=0.0001B;20;30;-50;50;")
                 }
-                Unit::Counter(_) => {
+                Unit::Milliseconds => assert_eq!(
+                    with_thresholds(Perfdata::milliseconds("ms", 1)).to_string(),
+                    "'ms'=1ms;20;30;-50;50;"
+                ),
+                Unit::Microseconds => assert_eq!(
+                    with_thresholds(Perfdata::microseconds("us", 1)).to_string(),
+                    "'us'=1us;20;30;-50;50;"
+                ),
+                Unit::Kilobytes => assert_eq!(
+                    with_thresholds(Perfdata::kilobytes("kb", 1)).to_string(),
+                    "'kb'=1KB;20;30;-50;50;"
+                ),
+                Unit::Megabytes => assert_eq!(
+                    with_thresholds(Perfdata::megabytes("mb", 1)).to_string(),
+                    "'mb'=1MB;20;30;-50;50;"
+                ),
+                Unit::Gigabytes => assert_eq!(
+                    with_thresholds(Perfdata::gigabytes("gb", 1)).to_string(),
+                    "'gb'=1GB;20;30;-50;50;"
+                ),
+                Unit::Terabytes => assert_eq!(
+                    with_thresholds(Perfdata::terabytes("tb", 1)).to_string(),
+                    "'tb'=1TB;20;30;-50;50;"
+                ),
+                Unit::Kibibytes => assert_eq!(
+                    with_thresholds(Perfdata::kibibytes("kib", 1)).to_string(),
+                    "'kib'=1KiB;20;30;-50;50;"
+                ),
+                Unit::Mebibytes => assert_eq!(
+                    with_thresholds(Perfdata::mebibytes("mib", 1)).to_string(),
+                    "'mib'=1MiB;20;30;-50;50;"
+                ),
+                Unit::Gibibytes => assert_eq!(
+                    with_thresholds(Perfdata::gibibytes("gib", 1)).to_string(),
+                    "'gib'=1GiB;20;30;-50;50;"
+                ),
+                Unit::Tebibytes => assert_eq!(
+                    with_thresholds(Perfdata::tebibytes("tib", 1)).to_string(),
+                    "'tib'=1TiB;20;30;-50;50;"
+                ),
+                Unit::Counter => {
                     let counter = Perfdata::counter("counter", 12345)
                         .with_warn(warn)
                         .with_crit(crit)
@@ -305,29 +643,114 @@ mod tests {
                         .with_max(max);
                     assert_eq!(counter.to_string(), "'counter'=12345c;20;30;-50;50;")
                 }
-                Unit::Undetermined => {
-                    let undetermined = Perfdata::undetermined("undetermined")
-                        .with_warn(warn)
-                        .with_crit(crit)
-                        .with_min(min)
-                        .with_max(max);
-                    assert_eq!(undetermined.to_string(), "'undetermined'=U;20;30;-50;50;")
-                }
+                Unit::Custom(_) => assert_eq!(
+                    with_thresholds(Perfdata::custom("custom", 1, "req/s").unwrap()).to_string(),
+                    "'custom'=1req/s;20;30;-50;50;"
+                ),
             };
         }
+
+        let undetermined = with_thresholds(Perfdata::undetermined("undetermined"));
+        assert_eq!(undetermined.to_string(), "'undetermined'=U;20;30;-50;50;")
+    }
+
+    #[test]
+    fn test_custom_uom() {
+        let rpm = Perfdata::custom("fan", 1200, "rpm").unwrap();
+        let known = Perfdata::custom("rta", 12, "ms").unwrap();
+        let none = Perfdata::custom("users", 3, "").unwrap();
+
+        assert_eq!(rpm.uom(), "rpm");
+        assert_eq!(rpm.value(), Some(1200.0));
+        assert_eq!(known, Perfdata::milliseconds("rta", 12));
+        assert_eq!(known.uom(), "ms");
+        assert_eq!(none, Perfdata::unitless("users", 3));
+        assert_eq!(none.uom(), "");
+
+        for uom in ["5", "k;x", "it's", "\"", "a b", "x=y"] {
+            assert_eq!(
+                Perfdata::custom("a", 1, uom),
+                Err(InvalidUnitError(uom.to_string()))
+            );
+        }
+        assert_eq!(
+            Perfdata::custom("a", 1, "5;x'").unwrap_err().to_string(),
+            "invalid unit of measurement `5;x'`"
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let warn = ThresholdRange::above_pos(20);
+        let crit = ThresholdRange::inside(30, 40);
+        let full = Perfdata::percentage("full", 10)
+            .with_warn(warn)
+            .with_crit(crit)
+            .with_min(0)
+            .with_max(100);
+        let empty = Perfdata::bytes("empty", 10);
+
+        assert_eq!(full.unit(), &Unit::Percentage);
+        assert_eq!(full.warn(), Some(warn));
+        assert_eq!(full.crit(), Some(crit));
+        assert_eq!(full.min(), Some(0.0));
+        assert_eq!(full.max(), Some(100.0));
+
+        assert_eq!(empty.unit(), &Unit::Bytes);
+        assert_eq!(empty.warn(), None);
+        assert_eq!(empty.crit(), None);
+        assert_eq!(empty.min(), None);
+        assert_eq!(empty.max(), None);
+    }
+
+    #[test]
+    fn test_owned_label() {
+        let owned = (0..3)
+            .map(|i| Perfdata::unitless(format!("disk_{}", i), i))
+            .collect::<Vec<_>>();
+
+        let input = String::from("borrowed");
+        let borrowed = Perfdata::unitless(input.as_str(), 10);
+        let detached = borrowed.clone().into_owned();
+        drop(input);
+
+        assert_eq!(owned[2].label(), "disk_2");
+        assert_eq!(detached.label(), "borrowed");
+        assert!(matches!(detached.label, Cow::Owned(_)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_owned() {
+        let perfdata = Perfdata::percentage("used", 42)
+            .with_warn(ThresholdRange::above_pos(80))
+            .with_min(0)
+            .with_max(100);
+
+        let json = serde_json::to_string(&perfdata).unwrap();
+        // Deserialization from a reader can't borrow from the input
+        let parsed: Perfdata<'static> = serde_json::from_reader(json.as_bytes()).unwrap();
+
+        assert_eq!(perfdata, parsed);
+    }
+
+    #[test]
+    fn test_format_escaped_label() {
+        assert_eq!(Perfdata::unitless("it's", 1).to_string(), "'it''s'=1;");
+        assert_eq!(Perfdata::unitless("'", 1).to_string(), "''''=1;");
     }
 
     #[test]
     fn test_warn_crit() {
-        let warn = Perfdata::unit("warn", 10)
+        let warn = Perfdata::unitless("warn", 10)
             .with_warn(ThresholdRange::above_pos(5))
             .with_crit(ThresholdRange::above_pos(15));
 
-        let crit = Perfdata::unit("warn", 20)
+        let crit = Perfdata::unitless("warn", 20)
             .with_warn(ThresholdRange::above_pos(5))
             .with_crit(ThresholdRange::above_pos(15));
 
-        let no_thresholds = Perfdata::unit("no_thresholds", 30);
+        let no_thresholds = Perfdata::unitless("no_thresholds", 30);
         let undetermined = Perfdata::undetermined("undetermined")
             .with_warn(ThresholdRange::above_pos(20))
             .with_crit(ThresholdRange::above_pos(20));
@@ -344,4 +767,105 @@ mod tests {
         assert!(!undetermined.is_warn());
         assert!(!undetermined.is_crit());
     }
+
+    #[test]
+    fn test_validate() {
+        let valid = Perfdata::percentage("valid", 50).with_min(0).with_max(100);
+        let above_max = Perfdata::unitless("above", 11).with_min(20).with_max(10);
+        let percentage = Perfdata::percentage("pct", 120).with_min(-1);
+        let counter = Perfdata::counter("counter", -1);
+        let nan = Perfdata::unitless("nan", f64::NAN)
+            .with_warn(ThresholdRange::above(f64::NAN))
+            .with_crit(ThresholdRange::below(1))
+            .with_max(f64::INFINITY);
+
+        assert!(valid.validate().is_empty());
+        assert!(Perfdata::undetermined("undetermined").validate().is_empty());
+        assert_eq!(
+            above_max.validate(),
+            [
+                PerfdataViolation::MinAboveMax {
+                    min: 20.0,
+                    max: 10.0
+                },
+                PerfdataViolation::BelowMin {
+                    value: 11.0,
+                    min: 20.0
+                },
+                PerfdataViolation::AboveMax {
+                    value: 11.0,
+                    max: 10.0
+                },
+            ]
+        );
+        assert_eq!(
+            percentage.validate(),
+            [
+                PerfdataViolation::PercentageOutOfRange {
+                    field: PerfdataField::Value,
+                    value: 120.0
+                },
+                PerfdataViolation::PercentageOutOfRange {
+                    field: PerfdataField::Min,
+                    value: -1.0
+                },
+            ]
+        );
+        assert_eq!(
+            counter.validate(),
+            [PerfdataViolation::NegativeCounter(-1.0)]
+        );
+        assert_eq!(
+            nan.validate(),
+            [
+                PerfdataViolation::NotFinite(PerfdataField::Value),
+                PerfdataViolation::NotFinite(PerfdataField::Warn),
+                PerfdataViolation::NotFinite(PerfdataField::Max),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unit_conversion() {
+        let rta = Perfdata::milliseconds("rta", 1500)
+            .with_warn(ThresholdRange::above_pos(1000))
+            .with_min(0);
+        let used = Perfdata::kilobytes("used", 2048).with_max(4096);
+
+        assert_eq!(
+            rta.clone().into_base_unit(),
+            Perfdata::seconds("rta", 1.5)
+                .with_warn(ThresholdRange::above_pos(1))
+                .with_min(0)
+        );
+        assert_eq!(
+            rta.clone().into_unit(Unit::Microseconds).unwrap().value(),
+            Some(1_500_000.0)
+        );
+        assert_eq!(
+            used.clone().into_unit(Unit::Megabytes),
+            Ok(Perfdata::megabytes("used", 2.048).with_max(4.096))
+        );
+        assert_eq!(
+            Perfdata::kibibytes("used", 2048).into_unit(Unit::Mebibytes),
+            Ok(Perfdata::mebibytes("used", 2))
+        );
+        assert_eq!(Unit::Mebibytes.convert(1.0, &Unit::Kibibytes), Ok(1024.0));
+        assert_eq!(Unit::Gigabytes.to_base(1.5), 1_500_000_000.0);
+        assert_eq!(
+            Perfdata::custom("fan", 1200, "rpm")
+                .unwrap()
+                .into_base_unit()
+                .unit(),
+            &Unit::Custom("rpm".to_string())
+        );
+
+        let err = rta.into_unit(Unit::Bytes).unwrap_err();
+        assert_eq!(err.from(), &Unit::Milliseconds);
+        assert_eq!(
+            err.to_string(),
+            "can't convert unit Milliseconds into Bytes"
+        );
+        assert!(Unit::Percentage.convert(1.0, &Unit::None).is_err());
+    }
 }
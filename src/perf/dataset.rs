@@ -1,10 +1,16 @@
+use crate::error::PerfdataViolation;
 use crate::monitoring_status::MonitoringStatus;
-use crate::Perfdata;
+use crate::{Perfdata, StatusPolicy};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 
 /// A PerfdataSet is a collection of Perfdata.
 /// It can be built via `PerfdataSet::new()`, from Iterators with `Item=<Perfdata>`, or from a
 /// `Vec<Perfdata>`.
+///
+/// With the `serde` feature it is serialized as a sequence of [Perfdata].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 #[derive(Debug, Default, PartialEq)]
 pub struct PerfdataSet<'a> {
     data: Vec<Perfdata<'a>>,
@@ -21,6 +27,11 @@ impl<'a> PerfdataSet<'a> {
         self.data.push(pd);
     }
 
+    /// Returns the number of contained `Perfdata`
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
     /// Returns true if the `PerfdataSet` does not contain any `Perfdata`
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
@@ -53,16 +64,41 @@ impl<'a> PerfdataSet<'a> {
         self.warning().next().is_some()
     }
 
-    /// Returns the MonitoringStatus reflecting the worst status based on Thresholds
-    /// Critical is worse than Warning is worse than OK
+    /// Converts the `PerfdataSet` into one which owns all contained `Perfdata`, and as such is no
+    /// longer bound to the lifetime of the input it was parsed from
+    pub fn into_owned(self) -> PerfdataSet<'static> {
+        self.data.into_iter().map(Perfdata::into_owned).collect()
+    }
+
+    /// Converts all contained `Perfdata` into their base unit (see [Perfdata::into_base_unit()]),
+    /// such that values of the same quantity can be compared, e.g. `1500ms` and `1.5s`
+    pub fn normalize(self) -> Self {
+        self.data
+            .into_iter()
+            .map(Perfdata::into_base_unit)
+            .collect()
+    }
+
+    /// Returns the MonitoringStatus reflecting the worst status based on Thresholds, aggregated by
+    /// the default [StatusPolicy], i.e. in the order [StatusPolicy::ICINGA_ORDER] where Critical is
+    /// worse than Unknown is worse than Warning is worse than OK
     pub fn status(&self) -> MonitoringStatus {
-        if self.has_critical() {
-            MonitoringStatus::Critical
-        } else if self.has_warning() {
-            MonitoringStatus::Warning
-        } else {
-            MonitoringStatus::OK
-        }
+        self.status_with(&StatusPolicy::default())
+    }
+
+    /// Checks all contained `Perfdata` with [Perfdata::validate()], and returns the violations
+    /// together with the index of the violating `Perfdata`
+    pub fn validate(&self) -> Vec<(usize, PerfdataViolation)> {
+        self.data
+            .iter()
+            .enumerate()
+            .flat_map(|(i, pd)| pd.validate().into_iter().map(move |v| (i, v)))
+            .collect()
+    }
+
+    /// Returns the MonitoringStatus aggregated by the given [StatusPolicy]
+    pub fn status_with(&self, policy: &StatusPolicy) -> MonitoringStatus {
+        policy.status(self)
     }
 }
 
@@ -80,6 +116,21 @@ impl<'a> FromIterator<Perfdata<'a>> for PerfdataSet<'a> {
     }
 }
 
+impl<'a> Extend<Perfdata<'a>> for PerfdataSet<'a> {
+    fn extend<T: IntoIterator<Item = Perfdata<'a>>>(&mut self, iter: T) {
+        self.data.extend(iter)
+    }
+}
+
+impl<'a> IntoIterator for PerfdataSet<'a> {
+    type Item = Perfdata<'a>;
+    type IntoIter = std::vec::IntoIter<Perfdata<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
 impl<'a> Display for PerfdataSet<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         for (i, pd) in self.data.iter().enumerate() {
@@ -108,7 +159,7 @@ mod tests {
             .with_min(-100)
             .with_max(100);
 
-        let pdo = Perfdata::unit("unit", 50);
+        let pdo = Perfdata::unitless("unit", 50);
         let pdu = Perfdata::undetermined("undetermined");
 
         let mut pds = PerfdataSet::new();
@@ -120,7 +171,7 @@ mod tests {
 
         // Act
         let result = pds.to_string();
-        let expected = "'bytes'=42b;@100;23;-100;100; 'unit'=50; 'undetermined'=U;";
+        let expected = "'bytes'=42B;@100;23;-100;100; 'unit'=50; 'undetermined'=U;";
 
         let empty_result = empty_pds.to_string();
 
@@ -133,9 +184,9 @@ mod tests {
     fn test_degraded() {
         let val = 10;
         let pds = vec![
-            Perfdata::unit("critical", val).with_crit(ThresholdRange::above_pos(0)),
-            Perfdata::unit("warn", val).with_warn(ThresholdRange::above_pos(0)),
-            Perfdata::unit("ok", val),
+            Perfdata::unitless("critical", val).with_crit(ThresholdRange::above_pos(0)),
+            Perfdata::unitless("warn", val).with_warn(ThresholdRange::above_pos(0)),
+            Perfdata::unitless("ok", val),
         ];
 
         let pds_crit: PerfdataSet = pds[..].iter().cloned().collect();
@@ -151,4 +202,25 @@ mod tests {
         assert_eq!(pds_ok.critical().count(), 0);
         assert_eq!(pds_ok.warning().count(), 0);
     }
+
+    #[test]
+    fn test_normalize() {
+        let pds: PerfdataSet = vec![
+            Perfdata::milliseconds("a", 1500),
+            Perfdata::seconds("b", 1.5),
+            Perfdata::mebibytes("c", 2).with_crit(ThresholdRange::above_pos(1)),
+            Perfdata::percentage("d", 50),
+        ]
+        .into();
+
+        let normalized: Vec<Perfdata> = pds.normalize().into_iter().collect();
+
+        assert_eq!(normalized[0], Perfdata::seconds("a", 1.5));
+        assert_eq!(normalized[1], Perfdata::seconds("b", 1.5));
+        assert_eq!(
+            normalized[2],
+            Perfdata::bytes("c", 2097152).with_crit(ThresholdRange::above_pos(1048576))
+        );
+        assert_eq!(normalized[3], Perfdata::percentage("d", 50));
+    }
 }
@@ -0,0 +1,325 @@
+use crate::perf::{NumberFormat, Perfdata, Unit, Value};
+use crate::thresholds::ThresholdRange;
+use std::fmt::{Display, Formatter};
+
+// Notes:
+//     values are converted into their base unit first, and then scaled to the largest prefix
+//     which keeps them at or above 1. Durations of a minute or longer are split into days, hours,
+//     minutes and seconds. Numbers are always written with a `.` as decimal separator and without
+//     grouping, independent of the locale.
+
+const IEC_PREFIXES: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+const SI_PREFIXES: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+const DURATIONS: [(&str, i64); 4] = [("d", 86400), ("h", 3600), ("m", 60), ("s", 1)];
+// An f64 has at most 17 significant digits, so rounding to more decimal places changes nothing
+const MAX_PRECISION: usize = 17;
+
+/// The prefixes used by [Humanized] for bytes
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BytePrefixes {
+    /// Powers of 1024, e.g. `KiB` or `GiB`
+    #[default]
+    Iec,
+    /// Powers of 1000, e.g. `KB` or `GB`
+    Si,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Humanizable {
+    Value(Option<Value>),
+    Range(ThresholdRange),
+}
+
+/// `Humanized` formats a value or [ThresholdRange] of a [Unit] for humans, e.g. in the summary
+/// text of a check, while the [Perfdata] keeps the raw value.
+///
+/// Bytes are scaled to the largest fitting prefix like `5 GiB`, durations are written like
+/// `1m 23s` or `250ms`. Thresholds are written as comparison like `> 5 GiB` or
+/// `inside 1s..2s`, where a threshold alerting below 0 or above a limit is written as `> limit`.
+/// A threshold which never alerts, like `~:`, is written as `never`.
+///
+/// ```text
+/// let disk = Perfdata::bytes("disk", 5_368_709_120.0);
+/// format!("{} used", disk.humanize()) // 5 GiB used
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Humanized<'u> {
+    value: Humanizable,
+    unit: &'u Unit,
+    prefixes: BytePrefixes,
+    precision: usize,
+}
+
+impl<'u> Humanized<'u> {
+    fn new(value: Humanizable, unit: &'u Unit) -> Self {
+        Humanized {
+            value,
+            unit,
+            prefixes: BytePrefixes::default(),
+            precision: 2,
+        }
+    }
+
+    /// Humanize a value of the given [Unit]
+    pub fn value(value: Value, unit: &'u Unit) -> Self {
+        Self::new(Humanizable::Value(Some(value)), unit)
+    }
+
+    /// Humanize a [ThresholdRange] for values of the given [Unit]
+    pub fn threshold(range: ThresholdRange, unit: &'u Unit) -> Self {
+        Self::new(Humanizable::Range(range), unit)
+    }
+
+    /// Set the [BytePrefixes], [Iec](`BytePrefixes::Iec`) by default
+    #[must_use]
+    pub fn with_byte_prefixes(mut self, prefixes: BytePrefixes) -> Self {
+        self.prefixes = prefixes;
+        self
+    }
+
+    /// Set the maximum number of decimal places, 2 by default. Trailing zeros are omitted.
+    #[must_use]
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    fn fmt_value(&self, f: &mut Formatter<'_>, value: Value) -> std::fmt::Result {
+        if !value.is_finite() {
+            return write!(f, "{}", value);
+        }
+        match self.unit.base() {
+            Unit::Bytes => {
+                let prefixes = match self.prefixes {
+                    BytePrefixes::Iec => (1024.0, IEC_PREFIXES),
+                    BytePrefixes::Si => (1000.0, SI_PREFIXES),
+                };
+                self.fmt_bytes(f, self.unit.to_base(value), prefixes)
+            }
+            Unit::Seconds => self.fmt_duration(f, self.unit.to_base(value)),
+            Unit::Percentage => {
+                self.fmt_number(f, value)?;
+                f.write_str("%")
+            }
+            Unit::None | Unit::Counter => self.fmt_number(f, value),
+            _ => {
+                self.fmt_number(f, value)?;
+                write!(f, " {}", self.unit.uom())
+            }
+        }
+    }
+
+    fn fmt_bytes(
+        &self,
+        f: &mut Formatter<'_>,
+        bytes: Value,
+        (step, prefixes): (Value, [&str; 5]),
+    ) -> std::fmt::Result {
+        let mut scaled = bytes;
+        let mut prefix = prefixes[0];
+        for p in &prefixes[1..] {
+            if self.round(scaled.abs()) < step {
+                break;
+            }
+            scaled /= step;
+            prefix = p;
+        }
+        self.fmt_number(f, scaled)?;
+        write!(f, " {}", prefix)
+    }
+
+    fn fmt_duration(&self, f: &mut Formatter<'_>, seconds: Value) -> std::fmt::Result {
+        // The scale is chosen by the rounded value, such that e.g. 59.999s is written as 1m
+        let (scaled, uom) = match seconds.abs() {
+            s if self.round(s) >= 60.0 => return fmt_long_duration(f, seconds),
+            s if self.round(s) >= 1.0 || s == 0.0 => (seconds, "s"),
+            s if self.round(s * 1e3) >= 1.0 => (seconds * 1e3, "ms"),
+            _ => (seconds * 1e6, "us"),
+        };
+        self.fmt_number(f, scaled)?;
+        f.write_str(uom)
+    }
+
+    // Rounds to the precision, as the value will be written
+    fn round(&self, value: Value) -> Value {
+        let factor = Value::powi(10.0, self.precision.min(MAX_PRECISION) as i32);
+        (value * factor).round() / factor
+    }
+
+    fn fmt_number(&self, f: &mut Formatter<'_>, value: Value) -> std::fmt::Result {
+        NumberFormat::new()
+            .with_max_decimals(self.precision)
+            .fmt_number(f, value, &Unit::None)
+    }
+}
+
+// Durations of a minute or longer are written in whole seconds, e.g. `1h 1m 5s`
+fn fmt_long_duration(f: &mut Formatter<'_>, seconds: Value) -> std::fmt::Result {
+    if seconds < 0.0 {
+        f.write_str("-")?;
+    }
+    let mut remaining = seconds.abs().round() as i64;
+    let mut first = true;
+    for (uom, length) in DURATIONS {
+        let amount = remaining / length;
+        remaining %= length;
+        if amount > 0 {
+            if !first {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}{}", amount, uom)?;
+            first = false;
+        }
+    }
+    Ok(())
+}
+
+impl Display for Humanized<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let range = match self.value {
+            Humanizable::Value(Some(value)) => return self.fmt_value(f, value),
+            Humanizable::Value(None) => return f.write_str("U"),
+            Humanizable::Range(range) => range,
+        };
+        let (start, end) = (range.start(), range.end());
+        match (range.alert_inside(), start, end) {
+            (false, Value::NEG_INFINITY, Value::INFINITY) => f.write_str("never"),
+            (true, Value::NEG_INFINITY, Value::INFINITY) => f.write_str("always"),
+            (false, s, Value::INFINITY) => {
+                f.write_str("< ")?;
+                self.fmt_value(f, s)
+            }
+            (false, s, e) if s == 0.0 || s == Value::NEG_INFINITY => {
+                f.write_str("> ")?;
+                self.fmt_value(f, e)
+            }
+            (true, Value::NEG_INFINITY, e) => {
+                f.write_str("<= ")?;
+                self.fmt_value(f, e)
+            }
+            (true, s, Value::INFINITY) => {
+                f.write_str(">= ")?;
+                self.fmt_value(f, s)
+            }
+            (inside, s, e) => {
+                f.write_str(if inside { "inside " } else { "outside " })?;
+                self.fmt_value(f, s)?;
+                f.write_str("..")?;
+                self.fmt_value(f, e)
+            }
+        }
+    }
+}
+
+impl<'a> Perfdata<'a> {
+    /// Formats the value for humans, see [Humanized]
+    pub fn humanize(&self) -> Humanized<'_> {
+        Humanized::new(Humanizable::Value(self.value()), self.unit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes() {
+        let disk = Perfdata::bytes("disk", 5_368_709_120.0);
+        let used = Perfdata::megabytes("used", 1536);
+
+        assert_eq!(disk.humanize().to_string(), "5 GiB");
+        assert_eq!(
+            disk.humanize()
+                .with_byte_prefixes(BytePrefixes::Si)
+                .to_string(),
+            "5.37 GB"
+        );
+        assert_eq!(used.humanize().with_precision(1).to_string(), "1.4 GiB");
+        assert_eq!(Humanized::value(512.0, &Unit::Bytes).to_string(), "512 B");
+        assert_eq!(
+            Humanized::value(-2048.0, &Unit::Bytes).to_string(),
+            "-2 KiB"
+        );
+        assert_eq!(
+            Humanized::value(1023.999, &Unit::Bytes).to_string(),
+            "1 KiB"
+        );
+    }
+
+    #[test]
+    fn test_durations() {
+        let duration = |value: Value, unit: Unit| Humanized::value(value, &unit).to_string();
+
+        assert_eq!(duration(83.0, Unit::Seconds), "1m 23s");
+        assert_eq!(duration(90061.4, Unit::Seconds), "1d 1h 1m 1s");
+        assert_eq!(duration(3_600_000.0, Unit::Milliseconds), "1h");
+        assert_eq!(duration(12.345, Unit::Seconds), "12.35s");
+        assert_eq!(duration(0.25, Unit::Seconds), "250ms");
+        assert_eq!(duration(1500.0, Unit::Microseconds), "1.5ms");
+        assert_eq!(duration(0.5, Unit::Milliseconds), "500us");
+        assert_eq!(duration(0.0, Unit::Seconds), "0s");
+        assert_eq!(duration(59.999, Unit::Seconds), "1m");
+        assert_eq!(duration(0.999999, Unit::Seconds), "1s");
+    }
+
+    #[test]
+    fn test_other_units() {
+        assert_eq!(
+            Perfdata::percentage("pl", 12.5).humanize().to_string(),
+            "12.5%"
+        );
+        assert_eq!(
+            Perfdata::unitless("load", 1.0 / 3.0).humanize().to_string(),
+            "0.33"
+        );
+        assert_eq!(
+            Perfdata::counter("packets", 42).humanize().to_string(),
+            "42"
+        );
+        assert_eq!(
+            Perfdata::custom("fan", 1200, "rpm")
+                .unwrap()
+                .humanize()
+                .to_string(),
+            "1200 rpm"
+        );
+        assert_eq!(Perfdata::undetermined("temp").humanize().to_string(), "U");
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let threshold = |range: ThresholdRange| {
+            Humanized::threshold(range, &Unit::Gibibytes)
+                .with_precision(1)
+                .to_string()
+        };
+
+        assert_eq!(threshold(ThresholdRange::above_pos(5)), "> 5 GiB");
+        assert_eq!(threshold(ThresholdRange::above(0.5)), "> 512 MiB");
+        assert_eq!(threshold(ThresholdRange::below(1)), "< 1 GiB");
+        assert_eq!(threshold(ThresholdRange::below(0)), "< 0 B");
+        assert_eq!(
+            threshold(ThresholdRange::outside(
+                Value::NEG_INFINITY,
+                Value::INFINITY
+            )),
+            "never"
+        );
+        assert_eq!(
+            threshold(ThresholdRange::inside(Value::NEG_INFINITY, Value::INFINITY)),
+            "always"
+        );
+        assert_eq!(
+            threshold(ThresholdRange::outside(1, 2)),
+            "outside 1 GiB..2 GiB"
+        );
+        assert_eq!(
+            threshold(ThresholdRange::inside(1, 2)),
+            "inside 1 GiB..2 GiB"
+        );
+        assert_eq!(
+            threshold(ThresholdRange::inside(Value::NEG_INFINITY, 2.0)),
+            "<= 2 GiB"
+        );
+    }
+}
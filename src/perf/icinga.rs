@@ -0,0 +1,348 @@
+// Source: https://icinga.com/docs/icinga-2/latest/doc/12-icinga2-api/#performance-data
+// Icinga2 returns performance data as `PerfdataValue` objects, or as compact strings:
+// {"counter":false,"crit":0.5,"label":"rta","max":null,"min":0.0,"type":"PerfdataValue",
+//     "unit":"seconds","value":0.012,"warn":"@0.1:0.2"}
+// Notes:
+//     values keep their unit, so that serializing and deserializing gives back the same Perfdata.
+//     The unit is written as UOM like `ms` or `c` for counters, the same way as [Unit] itself is
+//     serialized. Besides UOMs, the unit names used by Icinga2 for its normalized values like
+//     `seconds`, `bytes` or `percent` are deserialized. Thresholds are numbers if they are a plain
+//     upper limit, and strings in the Nagios range format otherwise. Keys are sorted like in
+//     Icinga2.
+//     telling apart these representations requires a self-describing format like JSON, so formats
+//     which are not human readable, like bincode, use a struct of the fields of Perfdata instead.
+
+use crate::error::InvalidUnitError;
+use crate::perf::{Perfdata, Unit, Value};
+use crate::thresholds::ThresholdRange;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const TYPE: &str = "PerfdataValue";
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Threshold {
+    Limit(Value),
+    Range(String),
+}
+
+impl From<ThresholdRange> for Threshold {
+    fn from(range: ThresholdRange) -> Self {
+        match range.upper_limit() {
+            Some(limit) => Threshold::Limit(limit),
+            None => Threshold::Range(range.to_string()),
+        }
+    }
+}
+
+impl TryFrom<Threshold> for ThresholdRange {
+    type Error = String;
+
+    fn try_from(threshold: Threshold) -> Result<Self, Self::Error> {
+        match threshold {
+            Threshold::Limit(limit) => Ok(ThresholdRange::above_pos(limit)),
+            Threshold::Range(range) => range.parse().map_err(|e| format!("{}", e)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PerfdataValueRef<'s> {
+    counter: bool,
+    crit: Option<Threshold>,
+    label: &'s str,
+    max: Option<Value>,
+    min: Option<Value>,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    unit: &'s Unit,
+    value: Option<Value>,
+    warn: Option<Threshold>,
+}
+
+#[derive(Deserialize)]
+struct PerfdataValue {
+    #[serde(default)]
+    counter: bool,
+    #[serde(default)]
+    crit: Option<Threshold>,
+    label: String,
+    #[serde(default)]
+    max: Option<Value>,
+    #[serde(default)]
+    min: Option<Value>,
+    #[serde(default)]
+    unit: Option<Unit>,
+    #[serde(default)]
+    value: Option<Value>,
+    #[serde(default)]
+    warn: Option<Threshold>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PerfdataRepr {
+    Compact(String),
+    Value(PerfdataValue),
+}
+
+#[derive(Serialize)]
+struct PerfdataFieldsRef<'s> {
+    label: &'s str,
+    value: Option<Value>,
+    unit: &'s Unit,
+    warn: Option<ThresholdRange>,
+    crit: Option<ThresholdRange>,
+    min: Option<Value>,
+    max: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct PerfdataFields {
+    label: String,
+    value: Option<Value>,
+    unit: Unit,
+    warn: Option<ThresholdRange>,
+    crit: Option<ThresholdRange>,
+    min: Option<Value>,
+    max: Option<Value>,
+}
+
+// Names of the normalized units of Icinga2
+fn unit_from_name(name: &str) -> Result<Unit, InvalidUnitError> {
+    match name {
+        "seconds" => Ok(Unit::Seconds),
+        "bytes" => Ok(Unit::Bytes),
+        "percent" => Ok(Unit::Percentage),
+        uom => Unit::custom(uom),
+    }
+}
+
+/// [Perfdata] is serialized like a `PerfdataValue` of the Icinga2 API, but keeps its unit instead
+/// of converting the values into the base unit. Formats which are not human readable get a plain
+/// struct of the fields.
+impl Serialize for Perfdata<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return PerfdataFieldsRef {
+                label: self.label(),
+                value: self.value(),
+                unit: self.unit(),
+                warn: self.warn(),
+                crit: self.crit(),
+                min: self.min(),
+                max: self.max(),
+            }
+            .serialize(serializer);
+        }
+        PerfdataValueRef {
+            counter: self.unit() == &Unit::Counter,
+            crit: self.crit().map(Threshold::from),
+            label: self.label(),
+            max: self.max(),
+            min: self.min(),
+            kind: TYPE,
+            unit: self.unit(),
+            value: self.value(),
+            warn: self.warn().map(Threshold::from),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// [Perfdata] is deserialized from a `PerfdataValue` of the Icinga2 API, or from its compact
+/// string form like `rta=12ms;100;200`. Formats which are not human readable expect the plain
+/// struct of the fields.
+impl<'de> Deserialize<'de> for Perfdata<'_> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            let fields = PerfdataFields::deserialize(deserializer)?;
+            let mut pd = Perfdata::new(fields.label.into(), fields.value, fields.unit);
+            if let Some(warn) = fields.warn {
+                pd = pd.with_warn(warn);
+            }
+            if let Some(crit) = fields.crit {
+                pd = pd.with_crit(crit);
+            }
+            if let Some(min) = fields.min {
+                pd = pd.with_min(min);
+            }
+            if let Some(max) = fields.max {
+                pd = pd.with_max(max);
+            }
+            return Ok(pd);
+        }
+        let value = match PerfdataRepr::deserialize(deserializer)? {
+            PerfdataRepr::Compact(s) => {
+                return Perfdata::try_from(s).map_err(D::Error::custom);
+            }
+            PerfdataRepr::Value(value) => value,
+        };
+
+        let unit = match value.unit {
+            _ if value.counter => Unit::Counter,
+            Some(unit) => unit,
+            None => Unit::None,
+        };
+        let threshold = |t: Option<Threshold>| t.map(ThresholdRange::try_from).transpose();
+        let mut pd = match value.value {
+            Some(v) => Perfdata::new(value.label.into(), Some(v), unit),
+            None => Perfdata::undetermined(value.label),
+        };
+        if let Some(warn) = threshold(value.warn).map_err(D::Error::custom)? {
+            pd = pd.with_warn(warn);
+        }
+        if let Some(crit) = threshold(value.crit).map_err(D::Error::custom)? {
+            pd = pd.with_crit(crit);
+        }
+        if let Some(min) = value.min {
+            pd = pd.with_min(min);
+        }
+        if let Some(max) = value.max {
+            pd = pd.with_max(max);
+        }
+        Ok(pd)
+    }
+}
+
+/// [Unit] is serialized as its UOM, e.g. `ms`
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.uom())
+    }
+}
+
+/// [Unit] is deserialized from its UOM, e.g. `ms`, or from the name of a normalized unit of
+/// Icinga2, e.g. `seconds`
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let uom = String::deserialize(deserializer)?;
+        unit_from_name(&uom).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PerfdataSet;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialize_perfdata_value() {
+        let pd = Perfdata::milliseconds("rta", 12)
+            .with_warn(ThresholdRange::inside(100, 200))
+            .with_crit(ThresholdRange::above_pos(500))
+            .with_min(0);
+        let counter = Perfdata::counter("packets", 12);
+        let undetermined = Perfdata::undetermined("temp");
+
+        assert_eq!(
+            serde_json::to_string(&pd).unwrap(),
+            r#"{"counter":false,"crit":500.0,"label":"rta","max":null,"min":0.0,"type":"PerfdataValue","unit":"ms","value":12.0,"warn":"@100:200"}"#
+        );
+        assert_eq!(
+            serde_json::to_value(&counter).unwrap(),
+            json!({"counter": true, "crit": null, "label": "packets", "max": null, "min": null,
+                "type": "PerfdataValue", "unit": "c", "value": 12.0, "warn": null})
+        );
+        assert_eq!(
+            serde_json::to_value(&undetermined).unwrap()["value"],
+            json!(null)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_perfdata_value() {
+        let json = json!([
+            {"counter": false, "crit": 0.5, "label": "rta", "max": null, "min": 0.0,
+                "type": "PerfdataValue", "unit": "seconds", "value": 0.012, "warn": "@0.1:0.2"},
+            {"label": "bytes_in", "counter": true, "value": 1024},
+            {"label": "size", "unit": "KB", "value": 3},
+            "'used disk'=80%;90;95",
+        ]);
+
+        let pds: Vec<Perfdata> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(
+            pds,
+            [
+                Perfdata::seconds("rta", 0.012)
+                    .with_warn(ThresholdRange::inside(0.1, 0.2))
+                    .with_crit(ThresholdRange::above_pos(0.5))
+                    .with_min(0),
+                Perfdata::counter("bytes_in", 1024),
+                Perfdata::kilobytes("size", 3),
+                Perfdata::percentage("used disk", 80)
+                    .with_warn(ThresholdRange::above_pos(90))
+                    .with_crit(ThresholdRange::above_pos(95)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pds = [
+            Perfdata::milliseconds("rta", 12)
+                .with_warn(ThresholdRange::inside(100, 200))
+                .with_crit(ThresholdRange::above_pos(500))
+                .with_min(0),
+            Perfdata::kilobytes("size", 3).with_max(1024),
+            Perfdata::percentage("used", 80).with_warn(ThresholdRange::below(10)),
+            Perfdata::counter("packets", 12),
+            Perfdata::custom("fan", 1200, "rpm").unwrap(),
+            Perfdata::unitless("load", 0.5),
+            Perfdata::undetermined("temp"),
+        ];
+
+        for pd in pds {
+            let json = serde_json::to_string(&pd).unwrap();
+            assert_eq!(serde_json::from_str::<Perfdata>(&json).unwrap(), pd);
+        }
+    }
+
+    #[test]
+    fn test_non_self_describing() {
+        let pds: PerfdataSet = vec![
+            Perfdata::milliseconds("rta", 12)
+                .with_warn(ThresholdRange::inside(100, 200))
+                .with_crit(ThresholdRange::above_pos(500))
+                .with_min(0)
+                .with_max(1000),
+            Perfdata::custom("fan", 1200, "rpm").unwrap(),
+            Perfdata::undetermined("temp"),
+        ]
+        .into();
+
+        let bytes = bincode::serialize(&pds).unwrap();
+
+        assert_eq!(bincode::deserialize::<PerfdataSet>(&bytes).unwrap(), pds);
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let range = json!({"label": "a", "value": 1, "warn": "1:x"});
+        let compact = json!("a=");
+        let unit = json!({"label": "a", "value": 1, "unit": "5;x'"});
+
+        assert!(serde_json::from_value::<Perfdata>(range).is_err());
+        assert!(serde_json::from_value::<Perfdata>(compact).is_err());
+        assert!(serde_json::from_value::<Perfdata>(unit).is_err());
+    }
+
+    #[test]
+    fn test_unit() {
+        assert_eq!(
+            serde_json::to_string(&Unit::Milliseconds).unwrap(),
+            r#""ms""#
+        );
+        assert_eq!(
+            serde_json::from_str::<Unit>(r#""req/s""#).unwrap(),
+            Unit::Custom("req/s".to_string())
+        );
+        assert_eq!(
+            serde_json::from_str::<Unit>(r#""percent""#).unwrap(),
+            Unit::Percentage
+        );
+    }
+}
@@ -1,9 +1,14 @@
 mod data;
 mod dataset;
+mod humanize;
+#[cfg(feature = "serde")]
+mod icinga;
+mod number_format;
+mod options;
 mod parser;
+pub(crate) mod tokenizer;
 
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use crate::error::{InvalidUnitError, UnitConversionError};
 use std::fmt::{Display, Formatter};
 
 #[cfg(test)]
@@ -11,29 +16,192 @@ use strum::EnumIter;
 
 pub use data::Perfdata;
 pub use dataset::PerfdataSet;
+pub use humanize::{BytePrefixes, Humanized};
+pub use number_format::{Formatted, NumberFormat};
+pub use options::ParseOptions;
 pub(crate) type Value = f64;
 
+// Characters which are not allowed in a UOM besides digits and whitespace, which would end the
+// datapoint
+const UOM_FORBIDDEN: [char; 4] = [';', '=', '\'', '"'];
+
+// UOMs must not contain numbers, semicolons, or quotes, so they can be parsed again
+pub(crate) fn is_forbidden_in_uom(c: char) -> bool {
+    c.is_ascii_digit() || c.is_whitespace() || UOM_FORBIDDEN.contains(&c)
+}
+
+/// The unit of measurement (UOM) of a [Perfdata] value, as described in the
+/// [Nagios Plugin Development Guidelines](https://nagios-plugins.org/doc/guidelines.html#AEN200).
+///
+/// Byte units are available with SI (powers of 1000) and IEC (powers of 1024) prefixes. Any UOM
+/// which is not known to this crate is kept verbatim as [Custom](`Unit::Custom`) unit.
 #[cfg_attr(test, derive(EnumIter))]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Unit {
-    None(Value),
-    Percentage(Value),
-    Seconds(Value),
-    Bytes(Value),
-    Counter(Value),
-    Undetermined,
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// No unit specified, a number of things (e.g. users, processes, load averages)
+    None,
+    /// Percentage (%)
+    Percentage,
+    /// Seconds (s)
+    Seconds,
+    /// Milliseconds (ms)
+    Milliseconds,
+    /// Microseconds (us)
+    Microseconds,
+    /// Bytes (B), also parsed from `b`
+    Bytes,
+    /// Kilobytes (KB), 1000 bytes. The prefix is decimal, 1024 bytes are
+    /// [Kibibytes](`Unit::Kibibytes`)
+    Kilobytes,
+    /// Megabytes (MB), 1000² bytes. The prefix is decimal, 1024² bytes are
+    /// [Mebibytes](`Unit::Mebibytes`)
+    Megabytes,
+    /// Gigabytes (GB), 1000³ bytes. The prefix is decimal, 1024³ bytes are
+    /// [Gibibytes](`Unit::Gibibytes`)
+    Gigabytes,
+    /// Terabytes (TB), 1000⁴ bytes. The prefix is decimal, 1024⁴ bytes are
+    /// [Tebibytes](`Unit::Tebibytes`)
+    Terabytes,
+    /// Kibibytes (KiB), 1024 bytes
+    Kibibytes,
+    /// Mebibytes (MiB), 1024² bytes
+    Mebibytes,
+    /// Gibibytes (GiB), 1024³ bytes
+    Gibibytes,
+    /// Tebibytes (TiB), 1024⁴ bytes
+    Tebibytes,
+    /// A continuous counter (c), such as bytes transmitted on an interface
+    Counter,
+    /// Any other UOM, kept verbatim so it can be reproduced in the output. Use
+    /// [Unit::custom()] to check that it can be parsed again.
+    Custom(String),
 }
 
-impl Display for Unit {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl Unit {
+    /// Create a `Unit` from an arbitrary UOM, like [From<&str>](`Unit::from()`) does, but fail if
+    /// the UOM contains digits, whitespace, semicolons, equals signs or quotes, which would make
+    /// the formatted [Perfdata] unparsable
+    pub fn custom(uom: &str) -> Result<Unit, InvalidUnitError> {
+        if uom.contains(is_forbidden_in_uom) {
+            return Err(InvalidUnitError(uom.to_string()));
+        }
+        Ok(Unit::from(uom))
+    }
+
+    /// The UOM as it appears in the string representation of a [Perfdata], e.g. `ms` or `%`.
+    /// Empty for [Unit::None].
+    pub fn uom(&self) -> &str {
+        match self {
+            Unit::None => "",
+            Unit::Percentage => "%",
+            Unit::Seconds => "s",
+            Unit::Milliseconds => "ms",
+            Unit::Microseconds => "us",
+            Unit::Bytes => "B",
+            Unit::Kilobytes => "KB",
+            Unit::Megabytes => "MB",
+            Unit::Gigabytes => "GB",
+            Unit::Terabytes => "TB",
+            Unit::Kibibytes => "KiB",
+            Unit::Mebibytes => "MiB",
+            Unit::Gibibytes => "GiB",
+            Unit::Tebibytes => "TiB",
+            Unit::Counter => "c",
+            Unit::Custom(uom) => uom,
+        }
+    }
+
+    /// The base unit of the same quantity, e.g. [Seconds](`Unit::Seconds`) for
+    /// [Milliseconds](`Unit::Milliseconds`). Units without prefix are their own base unit.
+    pub fn base(&self) -> Unit {
+        match self {
+            Unit::Milliseconds | Unit::Microseconds => Unit::Seconds,
+            Unit::Kilobytes
+            | Unit::Megabytes
+            | Unit::Gigabytes
+            | Unit::Terabytes
+            | Unit::Kibibytes
+            | Unit::Mebibytes
+            | Unit::Gibibytes
+            | Unit::Tebibytes => Unit::Bytes,
+            other => other.clone(),
+        }
+    }
+
+    /// Converts a value of this unit into the [base()](`Self::base()`) unit, e.g. `1500` ms
+    /// into `1.5` s
+    pub fn to_base(&self, value: Value) -> Value {
+        let (multiplier, divisor) = self.scale();
+        value * multiplier / divisor
+    }
+
+    /// Converts a value of this unit into the target unit, e.g. `2048` KB into `2.048` MB. Fails
+    /// if the units don't share the same [base()](`Self::base()`) unit.
+    pub fn convert(&self, value: Value, target: &Unit) -> Result<Value, UnitConversionError> {
+        if self.base() != target.base() {
+            return Err(UnitConversionError {
+                from: self.clone(),
+                to: target.clone(),
+            });
+        }
+        Ok(self.rescale(value, target))
+    }
+
+    // Converts a value into the target unit, which must share the same base unit
+    fn rescale(&self, value: Value, target: &Unit) -> Value {
+        let (multiplier, divisor) = self.scale();
+        let (target_multiplier, target_divisor) = target.scale();
+        value * (multiplier * target_divisor) / (divisor * target_multiplier)
+    }
+
+    // Multiplier and divisor to convert into the base unit, kept apart so that e.g. milliseconds
+    // are divided by 1000 instead of being multiplied with the inexact 0.001
+    fn scale(&self) -> (Value, Value) {
+        const KB: Value = 1000.0;
+        const KIB: Value = 1024.0;
         match self {
-            Unit::None(u) => write!(f, "{}", u),
-            Unit::Percentage(u) => write!(f, "{}%", u),
-            Unit::Seconds(u) => write!(f, "{}s", u),
-            Unit::Bytes(u) => write!(f, "{}b", u),
-            Unit::Counter(u) => write!(f, "{}c", u),
-            Unit::Undetermined => write!(f, "U"),
+            Unit::Milliseconds => (1.0, KB),
+            Unit::Microseconds => (1.0, KB.powi(2)),
+            Unit::Kilobytes => (KB, 1.0),
+            Unit::Megabytes => (KB.powi(2), 1.0),
+            Unit::Gigabytes => (KB.powi(3), 1.0),
+            Unit::Terabytes => (KB.powi(4), 1.0),
+            Unit::Kibibytes => (KIB, 1.0),
+            Unit::Mebibytes => (KIB.powi(2), 1.0),
+            Unit::Gibibytes => (KIB.powi(3), 1.0),
+            Unit::Tebibytes => (KIB.powi(4), 1.0),
+            _ => (1.0, 1.0),
         }
     }
 }
+
+impl From<&str> for Unit {
+    /// Known UOMs are mapped to their dedicated variant, everything else is kept as
+    /// [Custom](`Unit::Custom`) unit without further checks.
+    fn from(uom: &str) -> Self {
+        match uom {
+            "" => Unit::None,
+            "%" => Unit::Percentage,
+            "s" => Unit::Seconds,
+            "ms" => Unit::Milliseconds,
+            "us" => Unit::Microseconds,
+            "b" | "B" => Unit::Bytes,
+            "KB" => Unit::Kilobytes,
+            "MB" => Unit::Megabytes,
+            "GB" => Unit::Gigabytes,
+            "TB" => Unit::Terabytes,
+            "KiB" => Unit::Kibibytes,
+            "MiB" => Unit::Mebibytes,
+            "GiB" => Unit::Gibibytes,
+            "TiB" => Unit::Tebibytes,
+            "c" => Unit::Counter,
+            custom => Unit::Custom(custom.to_string()),
+        }
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.uom())
+    }
+}
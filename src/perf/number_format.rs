@@ -0,0 +1,273 @@
+use crate::perf::{Perfdata, PerfdataSet, Unit, Value};
+use crate::thresholds::ThresholdRange;
+use crate::{CheckResult, PluginOutput};
+use std::fmt::{Display, Formatter};
+
+// Notes:
+//     numbers are never written in scientific notation, as older Nagios versions and many
+//     graphing addons can't parse it. Rounding to decimals or significant digits omits trailing
+//     zeros, to keep the output short.
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+enum Precision {
+    #[default]
+    Shortest,
+    MaxDecimals(usize),
+    SignificantDigits(usize),
+}
+
+/// `NumberFormat` controls how the value, thresholds, min and max of [Perfdata] are written.
+///
+/// By default numbers are written in the shortest form which parses back to the same value, e.g.
+/// `0.30000000000000004` for a computed `0.1 + 0.2`. Rounding to a number of decimals or
+/// significant digits keeps the output short. Numbers are never written in scientific notation.
+///
+/// ```text
+/// let format = NumberFormat::new().with_max_decimals(2);
+/// let pd = Perfdata::seconds("time", 0.1 + 0.2);
+/// format!("{}", pd.display_with(format)) // 'time'=0.3s;
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct NumberFormat {
+    precision: Precision,
+    integer_counters: bool,
+}
+
+impl NumberFormat {
+    /// Create a new `NumberFormat`, writing numbers in their shortest form
+    pub fn new() -> Self {
+        NumberFormat::default()
+    }
+
+    /// Round numbers to at most the given number of decimal places
+    #[must_use]
+    pub fn with_max_decimals(mut self, decimals: usize) -> Self {
+        self.precision = Precision::MaxDecimals(decimals);
+        self
+    }
+
+    /// Round numbers to the given number of significant digits, e.g. `12345.6` to `12300` with 3
+    /// digits. A number of 0 behaves like 1. Subnormal numbers, like `5e-324`, are written as `0`.
+    #[must_use]
+    pub fn with_significant_digits(mut self, digits: usize) -> Self {
+        self.precision = Precision::SignificantDigits(digits.max(1));
+        self
+    }
+
+    /// Write the values of [Counter](`Unit::Counter`)s as whole numbers, regardless of the
+    /// precision
+    #[must_use]
+    pub fn with_integer_counters(mut self, integer: bool) -> Self {
+        self.integer_counters = integer;
+        self
+    }
+
+    pub(crate) fn fmt_number(
+        &self,
+        f: &mut Formatter<'_>,
+        value: Value,
+        unit: &Unit,
+    ) -> std::fmt::Result {
+        if !value.is_finite() {
+            return write!(f, "{}", value);
+        }
+        if self.integer_counters && *unit == Unit::Counter {
+            return fmt_rounded(f, value, 0);
+        }
+        match self.precision {
+            Precision::Shortest => write!(f, "{}", value),
+            Precision::MaxDecimals(decimals) => fmt_rounded(f, value, decimals),
+            // Subnormal numbers have lost precision anyway, and would be written with hundreds
+            // of zeros
+            Precision::SignificantDigits(_) if !value.is_normal() => f.write_str("0"),
+            Precision::SignificantDigits(digits) => {
+                let magnitude = value.abs().log10().floor() as i32;
+                let decimals = digits as i32 - 1 - magnitude;
+                if decimals >= 0 {
+                    fmt_rounded(f, value, decimals as usize)
+                } else {
+                    let factor = 10.0_f64.powi(-decimals);
+                    fmt_rounded(f, (value / factor).round() * factor, 0)
+                }
+            }
+        }
+    }
+}
+
+// Rounds to the number of decimals, omitting trailing zeros and the sign of zero
+fn fmt_rounded(f: &mut Formatter<'_>, value: Value, decimals: usize) -> std::fmt::Result {
+    let formatted = format!("{:.*}", decimals, value);
+    let trimmed = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+    match trimmed {
+        "-0" => f.write_str("0"),
+        number => f.write_str(number),
+    }
+}
+
+/// `Formatted` writes [Perfdata], a [PerfdataSet], a [ThresholdRange] or the output of a check with
+/// a [NumberFormat]. It is created with `display_with()`, e.g. [Perfdata::display_with()].
+#[derive(Debug, Copy, Clone)]
+pub struct Formatted<'f, T> {
+    inner: &'f T,
+    format: NumberFormat,
+}
+
+impl Display for Formatted<'_, Perfdata<'_>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_with(f, &self.format)
+    }
+}
+
+impl Display for Formatted<'_, PerfdataSet<'_>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, pd) in self.inner.data().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            pd.fmt_with(f, &self.format)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Formatted<'_, ThresholdRange> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_with(f, &self.format, &Unit::None)
+    }
+}
+
+impl Display for Formatted<'_, PluginOutput<'_>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_with(f, &self.format)
+    }
+}
+
+impl Display for Formatted<'_, CheckResult<'_>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.inner.output().fmt_with(f, &self.format)
+    }
+}
+
+impl<'a> Perfdata<'a> {
+    /// Formats the [Perfdata] with the given [NumberFormat] for its value, thresholds, min and
+    /// max
+    pub fn display_with(&self, format: NumberFormat) -> Formatted<'_, Self> {
+        Formatted {
+            inner: self,
+            format,
+        }
+    }
+}
+
+impl<'a> PerfdataSet<'a> {
+    /// Formats all [Perfdata] with the given [NumberFormat]
+    pub fn display_with(&self, format: NumberFormat) -> Formatted<'_, Self> {
+        Formatted {
+            inner: self,
+            format,
+        }
+    }
+}
+
+impl ThresholdRange {
+    /// Formats the bounds of the `ThresholdRange` with the given [NumberFormat]
+    pub fn display_with(&self, format: NumberFormat) -> Formatted<'_, Self> {
+        Formatted {
+            inner: self,
+            format,
+        }
+    }
+}
+
+impl<'a> PluginOutput<'a> {
+    /// Formats the `PluginOutput` with the given [NumberFormat] for its perfdata
+    pub fn display_with(&self, format: NumberFormat) -> Formatted<'_, Self> {
+        Formatted {
+            inner: self,
+            format,
+        }
+    }
+}
+
+impl<'a> CheckResult<'a> {
+    /// Formats the output of the `CheckResult` with the given [NumberFormat] for its perfdata
+    pub fn display_with(&self, format: NumberFormat) -> Formatted<'_, Self> {
+        Formatted {
+            inner: self,
+            format,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_decimals() {
+        let format = NumberFormat::new().with_max_decimals(2);
+        let pd = Perfdata::seconds("time", 0.1 + 0.2)
+            .with_warn(ThresholdRange::inside(1.0 / 3.0, 2.0 / 3.0))
+            .with_crit(ThresholdRange::above_pos(1))
+            .with_min(-0.001)
+            .with_max(10.125);
+
+        assert_eq!(
+            pd.to_string(),
+            "'time'=0.30000000000000004s;@0.3333333333333333:0.6666666666666666;1;-0.001;10.125;"
+        );
+        assert_eq!(
+            pd.display_with(format).to_string(),
+            "'time'=0.3s;@0.33:0.67;1;0;10.12;"
+        );
+    }
+
+    #[test]
+    fn test_significant_digits() {
+        let format = NumberFormat::new().with_significant_digits(3);
+        let pds: PerfdataSet = vec![
+            Perfdata::unitless("small", 0.000000123456),
+            Perfdata::unitless("large", 12345678901234567890.0),
+            Perfdata::unitless("rounded", 999.96),
+            Perfdata::unitless("zero", 0),
+            Perfdata::unitless("subnormal", 5e-324),
+            Perfdata::undetermined("undetermined"),
+        ]
+        .into();
+
+        assert_eq!(
+            pds.display_with(format).to_string(),
+            "'small'=0.000000123; 'large'=12300000000000000000; 'rounded'=1000; 'zero'=0; 'subnormal'=0; 'undetermined'=U;"
+        );
+        assert_eq!(
+            PerfdataSet::try_from(pds.display_with(format).to_string().as_str())
+                .unwrap()
+                .len(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_integer_counters() {
+        let format = NumberFormat::new()
+            .with_max_decimals(1)
+            .with_integer_counters(true);
+        let counter =
+            Perfdata::counter("packets", 1234567.8).with_warn(ThresholdRange::above_pos(2000000.4));
+        let gauge = Perfdata::unitless("gauge", 1.25);
+
+        assert_eq!(
+            counter.display_with(format).to_string(),
+            "'packets'=1234568c;2000000;;;;"
+        );
+        assert_eq!(gauge.display_with(format).to_string(), "'gauge'=1.2;");
+        assert_eq!(
+            ThresholdRange::below(0.25).display_with(format).to_string(),
+            "0.2:"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+/// `ParseOptions` control how tolerant the parser is towards perfdata which does not follow the
+/// [Nagios Reference](https://nagios-plugins.org/doc/guidelines.html#AEN200).
+///
+/// By default items without equals sign following the perfdata are skipped, and all other options
+/// are disabled, which is the behaviour of `try_from()`. Options are passed to
+/// [Perfdata::parse_with()](`crate::Perfdata::parse_with()`),
+/// [PerfdataSet::parse_with()](`crate::PerfdataSet::parse_with()`) or
+/// [PerfdataSet::parse_lenient()](`crate::PerfdataSet::parse_lenient()`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    decimal_comma: bool,
+    invalid_units: bool,
+    trailing_garbage: bool,
+    strict: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            decimal_comma: false,
+            invalid_units: false,
+            trailing_garbage: true,
+            strict: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Create the default `ParseOptions`
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Accept commas as decimal separator in values, thresholds and limits, e.g. `1,5`
+    #[must_use]
+    pub fn with_decimal_comma(mut self, allow: bool) -> Self {
+        self.decimal_comma = allow;
+        self
+    }
+
+    /// Accept units of measurement which contain numbers or quotes, and keep them as
+    /// [Custom](`crate::Unit::Custom`) unit
+    #[must_use]
+    pub fn with_invalid_units(mut self, allow: bool) -> Self {
+        self.invalid_units = allow;
+        self
+    }
+
+    /// Skip items without equals sign in a list of perfdata, e.g. text following the perfdata,
+    /// instead of reporting them as error. Enabled by default.
+    #[must_use]
+    pub fn with_trailing_garbage(mut self, allow: bool) -> Self {
+        self.trailing_garbage = allow;
+        self
+    }
+
+    /// Reject perfdata which can be parsed, but is not valid according to
+    /// [Perfdata::validate()](`crate::Perfdata::validate()`), e.g. with a value greater than max
+    #[must_use]
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Commas are accepted as decimal separator
+    pub fn decimal_comma(&self) -> bool {
+        self.decimal_comma
+    }
+
+    /// Units of measurement with forbidden characters are accepted
+    pub fn invalid_units(&self) -> bool {
+        self.invalid_units
+    }
+
+    /// Items without equals sign are skipped
+    pub fn trailing_garbage(&self) -> bool {
+        self.trailing_garbage
+    }
+
+    /// Invalid perfdata is rejected
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+}
@@ -1,8 +1,10 @@
-use crate::error::PerfdataParseError;
-use crate::perf::Perfdata;
-use crate::perf::Value;
+use crate::error::{offset_of, PerfdataField, PerfdataParseError, PerfdataParseErrorKind};
+use crate::perf::tokenizer::{find_label_delimiter, tokenize};
+use crate::perf::{is_forbidden_in_uom, Unit, Value};
+use crate::perf::{ParseOptions, Perfdata};
 use crate::thresholds::ThresholdRange;
 use crate::PerfdataSet;
+use std::borrow::Cow;
 use std::str::FromStr;
 
 // Source: https://nagios-plugins.org/doc/guidelines.html#AEN200
@@ -26,55 +28,105 @@ use std::str::FromStr;
 //         B - bytes (also KB, MB, TB)
 //         c - a continous counter (such as bytes transmitted on an interface)
 
-const LABEL_DELIMITER: char = '=';
+pub(super) const LABEL_DELIMITER: char = '=';
 const DATA_DELIMITER: char = ';';
-const LABEL_QUOTE: char = '\'';
-
-impl<'a> TryFrom<&'a str> for Perfdata<'a> {
-    type Error = PerfdataParseError;
-
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        // Labels can't contain equals signs, so the first one must delimit the label from the data
-        let (label, data) = value
-            .split_once(LABEL_DELIMITER)
-            .ok_or(PerfdataParseError::MissingEqualsSign)?;
-
-        let parsed_label = parse_label(label)?;
+pub(super) const LABEL_QUOTE: char = '\'';
+const ESCAPED_LABEL_QUOTE: &str = "''";
+
+impl<'a> Perfdata<'a> {
+    /// Parse a single [Perfdata] with the given [ParseOptions]. This is equivalent to
+    /// `Perfdata::try_from()` with default options.
+    pub fn parse_with(value: &'a str, options: &ParseOptions) -> Result<Self, PerfdataParseError> {
+        // Only quoted labels can contain equals signs, so the first one outside of quotes must
+        // delimit the label from the data. With unbalanced quotes, the first one is used such
+        // that the label can be rejected properly.
+        let delimiter_idx = find_label_delimiter(value)
+            .or_else(|| value.find(LABEL_DELIMITER))
+            .ok_or_else(|| {
+                PerfdataParseError::new(PerfdataParseErrorKind::MissingEqualsSign, value, value)
+            })?;
+        let (label, data) = (&value[..delimiter_idx], &value[delimiter_idx + 1..]);
+
+        let parsed_label = parse_label(label).map_err(|e| e.with_field(PerfdataField::Label))?;
 
         // Datapoints are the Value and Thresholds, delimited by ;
         let mut datapoints = data.split(DATA_DELIMITER);
 
         // The first datapoint must be the value, and it must not be empty
-        let value = next_datapoint(&mut datapoints).ok_or(PerfdataParseError::MissingValue)?;
+        let number = next_datapoint(&mut datapoints).ok_or_else(|| {
+            PerfdataParseError::new(PerfdataParseErrorKind::MissingValue, value, &data[..0])
+                .with_field(PerfdataField::Value)
+        })?;
 
         // With the label, and the value we can construct a simple Perfdata struct
-        let mut perfdata = parse_perfdata_with_unit(parsed_label, value)?;
+        let mut perfdata = parse_perfdata_with_unit(parsed_label, number, options)
+            .map_err(|e| e.with_offset(offset_of(value, number).unwrap_or_default()))?;
 
         // Warn, Crit, Min and Max are set conditionally, when they exist and are not empty
-        if let Some(warn) = next_datapoint(&mut datapoints) {
-            let parsed_warn = ThresholdRange::from_str(warn)?;
+        let warn = next_datapoint(&mut datapoints);
+        if let Some(warn) = warn {
+            let parsed_warn = parse_threshold(warn, options).map_err(|e| {
+                e.with_offset(offset_of(value, warn).unwrap_or_default())
+                    .with_field(PerfdataField::Warn)
+            })?;
             perfdata = perfdata.with_warn(parsed_warn);
         }
 
-        if let Some(crit) = next_datapoint(&mut datapoints) {
-            let parsed_crit = ThresholdRange::from_str(crit)?;
+        let crit = next_datapoint(&mut datapoints);
+        if let Some(crit) = crit {
+            let parsed_crit = parse_threshold(crit, options).map_err(|e| {
+                e.with_offset(offset_of(value, crit).unwrap_or_default())
+                    .with_field(PerfdataField::Crit)
+            })?;
             perfdata = perfdata.with_crit(parsed_crit);
         }
 
-        if let Some(min) = next_datapoint(&mut datapoints) {
-            let parsed_min: Value = min.parse()?;
+        let min = next_datapoint(&mut datapoints);
+        if let Some(min) = min {
+            let parsed_min =
+                parse_number(value, min, options).map_err(|e| e.with_field(PerfdataField::Min))?;
             perfdata = perfdata.with_min(parsed_min);
         }
 
-        if let Some(max) = next_datapoint(&mut datapoints) {
-            let parsed_max: Value = max.parse()?;
+        let max = next_datapoint(&mut datapoints);
+        if let Some(max) = max {
+            let parsed_max =
+                parse_number(value, max, options).map_err(|e| e.with_field(PerfdataField::Max))?;
             perfdata = perfdata.with_max(parsed_max);
         }
 
+        // Strict parsing rejects the first violation, pointing to the violating datapoint
+        if let Some(violation) = options
+            .strict()
+            .then(|| perfdata.validate().into_iter().next())
+            .flatten()
+        {
+            let field = violation.field();
+            let token = match field {
+                PerfdataField::Min => min,
+                PerfdataField::Max => max,
+                PerfdataField::Warn => warn,
+                PerfdataField::Crit => crit,
+                _ => Some(number),
+            };
+            let kind = PerfdataParseErrorKind::Invalid(violation);
+            return Err(
+                PerfdataParseError::new(kind, value, token.unwrap_or(number)).with_field(field),
+            );
+        }
+
         Ok(perfdata)
     }
 }
 
+impl<'a> TryFrom<&'a str> for Perfdata<'a> {
+    type Error = PerfdataParseError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Perfdata::parse_with(value, &ParseOptions::default())
+    }
+}
+
 impl<'a> TryFrom<&'a String> for Perfdata<'a> {
     type Error = PerfdataParseError;
 
@@ -83,53 +135,102 @@ impl<'a> TryFrom<&'a String> for Perfdata<'a> {
     }
 }
 
-fn parse_label(input: &str) -> Result<&str, PerfdataParseError> {
+impl TryFrom<String> for Perfdata<'static> {
+    type Error = PerfdataParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Perfdata::try_from(value.as_str()).map(Perfdata::into_owned)
+    }
+}
+
+fn parse_label(input: &str) -> Result<Cow<'_, str>, PerfdataParseError> {
     let mut label = input.trim();
+    let mut quoted = false;
 
     // labels can be surrounded by single quotes, and must do so, if the label contains a space
-    // as labels are stored as &str, we strip them before processing
-    if label.starts_with(LABEL_QUOTE) && label.ends_with(LABEL_QUOTE) {
-        label = &label[1..label.len() - 1]
+    // as labels are stored without them, we strip them before processing
+    if label.len() >= 2 && label.starts_with(LABEL_QUOTE) && label.ends_with(LABEL_QUOTE) {
+        label = &label[1..label.len() - 1];
+        quoted = true;
     }
 
-    // no quotes may be contained from here on
-    if label.contains(LABEL_QUOTE) {
-        return Err(PerfdataParseError::LabelContainsSingleQuote);
+    if label.is_empty() {
+        return Err(PerfdataParseError::new(
+            PerfdataParseErrorKind::MissingLabel,
+            input,
+            input.trim(),
+        ));
     }
 
-    if label.is_empty() {
-        return Err(PerfdataParseError::MissingLabel);
+    if !label.contains(LABEL_QUOTE) {
+        return Ok(Cow::Borrowed(label));
     }
 
-    Ok(label)
+    // inside a quoted label, a quote character is escaped by two single quotes
+    if !quoted || label.replace(ESCAPED_LABEL_QUOTE, "").contains(LABEL_QUOTE) {
+        return Err(PerfdataParseError::new(
+            PerfdataParseErrorKind::LabelContainsSingleQuote,
+            input,
+            input.trim(),
+        ));
+    }
+
+    Ok(Cow::Owned(label.replace(ESCAPED_LABEL_QUOTE, "'")))
 }
 
 fn parse_perfdata_with_unit<'a>(
-    label: &'a str,
-    value: &'a str,
+    label: Cow<'a, str>,
+    input: &'a str,
+    options: &ParseOptions,
 ) -> Result<Perfdata<'a>, PerfdataParseError> {
-    if value == "U" || value == "u" {
+    if input == "U" || input == "u" {
         return Ok(Perfdata::undetermined(label));
     }
 
-    let (value, unit) = value
-        .find(|c: char| c != '.' && c != '-' && !c.is_ascii_digit())
-        .map(|split_at| value.split_at(split_at))
-        .unwrap_or((value, ""));
+    let is_numeric = |c: char| {
+        c == '.' || c == '-' || c.is_ascii_digit() || (options.decimal_comma() && c == ',')
+    };
+    let (value, unit) = input
+        .find(|c: char| !is_numeric(c))
+        .map(|split_at| input.split_at(split_at))
+        .unwrap_or((input, ""));
+
+    // Without a leading number, like `inf` or `NaN`, the empty number fails to parse, and the
+    // whole input is reported as offending value
+    let parsed_value = parse_number(input, value, options).map_err(|e| {
+        let e = match value {
+            "" => PerfdataParseError::new(e.kind().clone(), input, input),
+            _ => e,
+        };
+        e.with_field(PerfdataField::Value)
+    })?;
+
+    // UOM must not contain numbers, semicolons, or quotes. Whitespace would end the datapoint
+    if !options.invalid_units() && unit.contains(is_forbidden_in_uom) {
+        let kind = PerfdataParseErrorKind::InvalidUnit(unit.to_string());
+        return Err(PerfdataParseError::new(kind, input, unit).with_field(PerfdataField::Unit));
+    }
+
+    let perfdata = Perfdata::new(label, Some(parsed_value), Unit::from(unit));
 
-    let parsed_value: Value = value.parse()?;
+    Ok(perfdata)
+}
 
-    let perfdata = match unit {
-        "" => Perfdata::unit(label, parsed_value),
-        "s" => Perfdata::seconds(label, parsed_value),
-        "b" => Perfdata::bytes(label, parsed_value),
-        "c" => Perfdata::counter(label, parsed_value),
-        "%" => Perfdata::percentage(label, parsed_value),
-        // TODO evaluate allowing all units?
-        _ => return Err(PerfdataParseError::UnknownUnit(unit.to_string())),
+// The number must be a subslice of the input, to determine its position for errors
+fn parse_number(
+    input: &str,
+    number: &str,
+    options: &ParseOptions,
+) -> Result<Value, PerfdataParseError> {
+    let parsed = if options.decimal_comma() && number.contains(',') {
+        number.replace(',', ".").parse()
+    } else {
+        number.parse()
     };
 
-    Ok(perfdata)
+    parsed.map_err(|e| {
+        PerfdataParseError::new(PerfdataParseErrorKind::ParseValueError(e), input, number)
+    })
 }
 
 fn next_datapoint<'a>(mut datapoints: impl Iterator<Item = &'a str>) -> Option<&'a str> {
@@ -161,37 +262,51 @@ impl FromStr for ThresholdRange {
     type Err = PerfdataParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.is_empty() {
-            return Err(PerfdataParseError::ThresholdEmpty);
-        }
+        parse_threshold(s, &ParseOptions::default())
+    }
+}
 
-        let mut range = s;
-        let inside = s.starts_with(INSIDE_MARKER);
-        if inside {
-            range = &s[1..];
-        }
+fn parse_threshold(s: &str, options: &ParseOptions) -> Result<ThresholdRange, PerfdataParseError> {
+    if s.is_empty() {
+        return Err(PerfdataParseError::new(
+            PerfdataParseErrorKind::ThresholdEmpty,
+            s,
+            s,
+        ));
+    }
 
-        let (start, end) = match range.split_once(RANGE_DELIMITER) {
-            Some((start, end)) => {
-                let parsed_start = parse_range(start, START_DEFAULT)?;
-                let parsed_end = parse_range(end, END_DEFAULT)?;
-                (parsed_start, parsed_end)
-            }
-            None => {
-                let parsed_end = parse_range(range, END_DEFAULT)?;
-                (START_DEFAULT, parsed_end)
-            }
-        };
+    let mut range = s;
+    let inside = s.starts_with(INSIDE_MARKER);
+    if inside {
+        range = &s[1..];
+    }
 
-        if inside {
-            Ok(ThresholdRange::inside(start, end))
-        } else {
-            Ok(ThresholdRange::outside(start, end))
+    let (start, end) = match range.split_once(RANGE_DELIMITER) {
+        Some((start, end)) => {
+            let parsed_start = parse_range(s, start, START_DEFAULT, options)?;
+            let parsed_end = parse_range(s, end, END_DEFAULT, options)?;
+            (parsed_start, parsed_end)
         }
+        None => {
+            let parsed_end = parse_range(s, range, END_DEFAULT, options)?;
+            (START_DEFAULT, parsed_end)
+        }
+    };
+
+    if inside {
+        Ok(ThresholdRange::inside(start, end))
+    } else {
+        Ok(ThresholdRange::outside(start, end))
     }
 }
 
-fn parse_range(range: &str, default: Value) -> Result<Value, PerfdataParseError> {
+// The range must be a subslice of the input, to determine its position for errors
+fn parse_range(
+    input: &str,
+    range: &str,
+    default: Value,
+    options: &ParseOptions,
+) -> Result<Value, PerfdataParseError> {
     if range.is_empty() {
         return Ok(default);
     }
@@ -199,39 +314,76 @@ fn parse_range(range: &str, default: Value) -> Result<Value, PerfdataParseError>
         return Ok(Value::NEG_INFINITY);
     }
 
-    Ok(range.parse()?)
+    parse_number(input, range, options)
+}
+
+impl<'a> PerfdataSet<'a> {
+    /// Parse a list of [Perfdata] with the given [ParseOptions]. This is equivalent to
+    /// `PerfdataSet::try_from()` with default options.
+    pub fn parse_with(s: &'a str, options: &ParseOptions) -> Result<Self, PerfdataParseError> {
+        parse_items(s, options).collect()
+    }
+
+    /// Parse a list of [Perfdata] with the given [ParseOptions], without failing on the first
+    /// invalid item. All valid items are collected into the `PerfdataSet`, and an error is
+    /// returned for each invalid item.
+    pub fn parse_lenient(s: &'a str, options: &ParseOptions) -> (Self, Vec<PerfdataParseError>) {
+        let mut perfdata = PerfdataSet::new();
+        let mut errors = Vec::new();
+
+        for item in parse_items(s, options) {
+            match item {
+                Ok(pd) => perfdata.add(pd),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        (perfdata, errors)
+    }
+}
+
+fn parse_items<'a>(
+    s: &'a str,
+    options: &ParseOptions,
+) -> impl Iterator<Item = Result<Perfdata<'a>, PerfdataParseError>> {
+    // Skipped garbage is not counted, so the index of an item is its position among the perfdata
+    let options = *options;
+    tokenize(s)
+        .filter(move |(_, token)| !options.trailing_garbage() || token.contains(LABEL_DELIMITER))
+        .enumerate()
+        .map(move |(index, (start, token))| {
+            Perfdata::parse_with(token, &options).map_err(|e| e.with_offset(start).with_item(index))
+        })
 }
 
 impl<'a> TryFrom<&'a str> for PerfdataSet<'a> {
     type Error = PerfdataParseError;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
-        let mut remainder = s.trim();
-        let mut perfdata = Self::new();
-
-        // Perfdata are delimited by spaces, but labels can contain spaces. To avoid handling that,
-        // first we search until the next equals sign, which are not allowed in labels.
-        while let Some(equals_idx) = remainder.find('=') {
-            // Then we search until the next space, or the end of the input.
-            if let Some(data_idx) = &remainder[equals_idx..].find(' ') {
-                let (left, right) = remainder.split_at(equals_idx + data_idx);
-                if !left.is_empty() {
-                    perfdata.add(Perfdata::try_from(left)?);
-                }
-                remainder = right;
-            } else {
-                perfdata.add(Perfdata::try_from(remainder)?);
-                remainder = "";
-            }
-        }
+        PerfdataSet::parse_with(s, &ParseOptions::default())
+    }
+}
 
-        Ok(perfdata)
+impl<'a> TryFrom<&'a String> for PerfdataSet<'a> {
+    type Error = PerfdataParseError;
+
+    fn try_from(s: &'a String) -> Result<Self, Self::Error> {
+        PerfdataSet::try_from(s.as_str())
+    }
+}
+
+impl TryFrom<String> for PerfdataSet<'static> {
+    type Error = PerfdataParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        PerfdataSet::try_from(s.as_str()).map(PerfdataSet::into_owned)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::PerfdataViolation;
     use crate::perf::Unit;
     use crate::thresholds::ThresholdRange;
     use strum::IntoEnumIterator;
@@ -242,32 +394,174 @@ mod tests {
 
         let parsed = PerfdataSet::try_from(list).unwrap();
         let expected: PerfdataSet = vec![
-            Perfdata::unit("label", 10)
+            Perfdata::unitless("label", 10)
                 .with_warn(ThresholdRange::above_pos(20))
                 .with_crit(ThresholdRange::above_pos(30))
                 .with_min(0)
                 .with_max(40),
             Perfdata::seconds("foo", 0),
-            Perfdata::unit("with space", 42),
-            Perfdata::unit("with two spaces", 2),
-            Perfdata::unit("with  ma ny   spaces", 6),
+            Perfdata::unitless("with space", 42),
+            Perfdata::unitless("with two spaces", 2),
+            Perfdata::unitless("with  ma ny   spaces", 6),
         ]
         .into_iter()
         .collect();
         assert_eq!(parsed, expected)
     }
 
+    #[test]
+    fn test_parse_list_quoting_and_whitespace() {
+        let nsclient = "'C:\\ used'=20.5GB;40;45;0;50 'C:\\ used %'=41%;80;90;0;100";
+        let snmp = "'ifInOctets=eth0'=123c\t'ifOutOctets=eth0'=456c\n'it''s = odd'=1";
+
+        let parsed_nsclient = PerfdataSet::try_from(nsclient).unwrap();
+        let parsed_snmp = PerfdataSet::try_from(snmp).unwrap();
+
+        let expected_nsclient: PerfdataSet = vec![
+            Perfdata::gigabytes("C:\\ used", 20.5)
+                .with_warn(ThresholdRange::above_pos(40))
+                .with_crit(ThresholdRange::above_pos(45))
+                .with_min(0)
+                .with_max(50),
+            Perfdata::percentage("C:\\ used %", 41)
+                .with_warn(ThresholdRange::above_pos(80))
+                .with_crit(ThresholdRange::above_pos(90))
+                .with_min(0)
+                .with_max(100),
+        ]
+        .into_iter()
+        .collect();
+        let expected_snmp: PerfdataSet = vec![
+            Perfdata::counter("ifInOctets=eth0", 123),
+            Perfdata::counter("ifOutOctets=eth0", 456),
+            Perfdata::unitless("it's = odd", 1),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(parsed_nsclient, expected_nsclient);
+        assert_eq!(parsed_snmp, expected_snmp);
+
+        let formatted = parsed_snmp.to_string();
+        assert_eq!(PerfdataSet::try_from(&formatted).unwrap(), expected_snmp);
+    }
+
+    #[test]
+    fn test_parse_list_trailing_garbage() {
+        let garbage = "label=1 garbage";
+        let leading = "garbage label=1";
+        let reject = ParseOptions::new().with_trailing_garbage(false);
+
+        let parsed = PerfdataSet::try_from(garbage).unwrap();
+        let parsed_leading = PerfdataSet::try_from(leading).unwrap();
+        let rejected = PerfdataSet::parse_with(garbage, &reject);
+
+        assert_eq!(parsed, vec![Perfdata::unitless("label", 1)].into());
+        // Unquoted labels may contain spaces, so leading text becomes part of the label
+        assert_eq!(
+            parsed_leading,
+            vec![Perfdata::unitless("garbage label", 1)].into()
+        );
+        assert_eq!(
+            rejected.unwrap_err().kind(),
+            &PerfdataParseErrorKind::MissingEqualsSign
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let list = "ok=1 bad=x;2 'also ok'=2s;3 worse=1;2;3;;x last=3";
+
+        let (parsed, errors) = PerfdataSet::parse_lenient(list, &ParseOptions::default());
+        let labels: Vec<&str> = parsed.data().map(|pd| pd.label()).collect();
+        let failed: Vec<Option<usize>> = errors.iter().map(|e| e.item()).collect();
+
+        assert_eq!(labels, ["ok", "also ok", "last"]);
+        assert_eq!(failed, [Some(1), Some(3)]);
+        assert_eq!(&list[errors[0].span()], "x");
+        assert_eq!(errors[1].field(), Some(PerfdataField::Max));
+
+        let garbage = "a=1 junk b=x";
+        let err = PerfdataSet::try_from(garbage).unwrap_err();
+        assert_eq!(err.item(), Some(1));
+        assert_eq!(&garbage[err.span()], "x");
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        let list = "ok=1;;;0;10 above=11;;;0;10 pct=50%;;;0;200";
+        let strict = ParseOptions::new().with_strict(true);
+
+        let (parsed, errors) = PerfdataSet::parse_lenient(list, &strict);
+
+        assert_eq!(PerfdataSet::try_from(list).unwrap().len(), 3);
+        assert_eq!(PerfdataSet::try_from(list).unwrap().validate().len(), 2);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(&list[errors[0].span()], "11");
+        assert_eq!(errors[0].field(), Some(PerfdataField::Value));
+        assert_eq!(&list[errors[1].span()], "200");
+        assert_eq!(
+            errors[1].kind(),
+            &PerfdataParseErrorKind::Invalid(PerfdataViolation::PercentageOutOfRange {
+                field: PerfdataField::Max,
+                value: 200.0
+            })
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "perfdata #1: value 11 is greater than max 10 in value at 18..20: `11`"
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let decimal_comma = "load=1,5;2,5;@3,5:4;0;10,0";
+        let invalid_unit = "temp=21C2";
+        let garbage = "a=1 b=2 (3 more)";
+
+        let decimal_options = ParseOptions::new().with_decimal_comma(true);
+        let unit_options = ParseOptions::new().with_invalid_units(true);
+        let garbage_options = ParseOptions::new().with_trailing_garbage(false);
+
+        let got_decimal = Perfdata::parse_with(decimal_comma, &decimal_options).unwrap();
+        let got_decimal_strict = Perfdata::try_from(decimal_comma);
+        let got_unit = Perfdata::parse_with(invalid_unit, &unit_options).unwrap();
+        let got_unit_strict = Perfdata::try_from(invalid_unit);
+        let got_garbage = PerfdataSet::try_from(garbage).unwrap();
+        let got_garbage_strict = PerfdataSet::parse_with(garbage, &garbage_options);
+
+        assert_eq!(
+            got_decimal,
+            Perfdata::unitless("load", 1.5)
+                .with_warn(ThresholdRange::above_pos(2.5))
+                .with_crit(ThresholdRange::inside(3.5, 4.0))
+                .with_min(0)
+                .with_max(10)
+        );
+        assert!(got_decimal_strict.is_err());
+        assert_eq!(
+            got_unit,
+            Perfdata::new("temp".into(), Some(21.0), Unit::from("C2"))
+        );
+        assert!(got_unit_strict.is_err());
+        assert_eq!(got_garbage.len(), 2);
+        assert_eq!(
+            got_garbage_strict.unwrap_err().kind(),
+            &PerfdataParseErrorKind::MissingEqualsSign
+        );
+    }
+
     #[test]
     fn test_parse_simple() {
         let perfdata = "label=42";
         let perfdata_long = "label2=10;20;30;0;100;";
 
-        let expected_long = Perfdata::unit("label2", 10)
+        let expected_long = Perfdata::unitless("label2", 10)
             .with_warn(ThresholdRange::above_pos(20))
             .with_crit(ThresholdRange::above_pos(30))
             .with_min(0)
             .with_max(100);
-        let expected = Perfdata::unit("label", 42);
+        let expected = Perfdata::unitless("label", 42);
 
         let got_long = Perfdata::try_from(perfdata_long).unwrap();
         let got = Perfdata::try_from(perfdata).unwrap();
@@ -282,58 +576,128 @@ mod tests {
         let empty_value = "label=";
         let empty_value_thresholds = "label=;20;30;0;100;";
         let space_unit = "label=1 s";
-        let unknown_unit = "label=1x";
+        let number_unit = "label=1x2";
+        let quote_unit = "label=1\"x";
 
         let got_no_equals = Perfdata::try_from(no_equals);
         let got_empty_value = Perfdata::try_from(empty_value);
         let got_empty_value_thresholds = Perfdata::try_from(empty_value_thresholds);
         let got_space_unit = Perfdata::try_from(space_unit);
-        let got_unknown_unit = Perfdata::try_from(unknown_unit);
+        let got_number_unit = Perfdata::try_from(number_unit);
+        let got_quote_unit = Perfdata::try_from(quote_unit);
 
         assert!(matches!(
-            got_no_equals,
-            Err(PerfdataParseError::MissingEqualsSign)
+            got_no_equals.unwrap_err().kind(),
+            PerfdataParseErrorKind::MissingEqualsSign
+        ));
+        assert!(matches!(
+            got_empty_value.unwrap_err().kind(),
+            PerfdataParseErrorKind::MissingValue
         ));
         assert!(matches!(
-            got_empty_value,
-            Err(PerfdataParseError::MissingValue)
+            got_empty_value_thresholds.unwrap_err().kind(),
+            PerfdataParseErrorKind::MissingValue
         ));
         assert!(matches!(
-            got_empty_value_thresholds,
-            Err(PerfdataParseError::MissingValue)
+            got_space_unit.unwrap_err().kind(),
+            PerfdataParseErrorKind::InvalidUnit(_)
         ));
         assert!(matches!(
-            got_space_unit,
-            Err(PerfdataParseError::UnknownUnit(_))
+            got_number_unit.unwrap_err().kind(),
+            PerfdataParseErrorKind::InvalidUnit(_)
         ));
         assert!(matches!(
-            got_unknown_unit,
-            Err(PerfdataParseError::UnknownUnit(_))
+            got_quote_unit.unwrap_err().kind(),
+            PerfdataParseErrorKind::InvalidUnit(_)
         ))
     }
 
+    #[test]
+    fn test_parse_non_numeric_values() {
+        for input in ["a=inf", "a=NaN", "a=infinity", "a=-inf"] {
+            let err = Perfdata::try_from(input).unwrap_err();
+
+            assert_eq!(err.field(), Some(PerfdataField::Value), "{}", input);
+            assert!(matches!(
+                err.kind(),
+                PerfdataParseErrorKind::ParseValueError(_)
+            ));
+            assert_eq!(err.span().start, 2);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_details() {
+        let bad_value = "label=abc;20;30";
+        let bad_min = "label=10;20;30;zero;100";
+        let bad_crit = "label=10;20;@5:x";
+        let bad_unit = "label=10m5";
+        let bad_label = "'la'bel'=10";
+        let list = "first=1 'second'=2;3 third=3;;;0;1O0 fourth=4";
+
+        let err_value = Perfdata::try_from(bad_value).unwrap_err();
+        let err_min = Perfdata::try_from(bad_min).unwrap_err();
+        let err_crit = Perfdata::try_from(bad_crit).unwrap_err();
+        let err_unit = Perfdata::try_from(bad_unit).unwrap_err();
+        let err_label = Perfdata::try_from(bad_label).unwrap_err();
+        let err_list = PerfdataSet::try_from(list).unwrap_err();
+
+        assert_eq!(err_value.field(), Some(PerfdataField::Value));
+        assert_eq!(err_value.token(), "abc");
+        assert_eq!(err_value.span(), 6..9);
+        assert_eq!(err_value.item(), None);
+
+        assert_eq!(err_min.field(), Some(PerfdataField::Min));
+        assert_eq!(err_min.token(), "zero");
+        assert_eq!(&bad_min[err_min.span()], "zero");
+
+        assert_eq!(err_crit.field(), Some(PerfdataField::Crit));
+        assert_eq!(err_crit.token(), "x");
+        assert_eq!(err_crit.span(), 15..16);
+
+        assert_eq!(err_unit.field(), Some(PerfdataField::Unit));
+        assert_eq!(err_unit.token(), "m5");
+        assert_eq!(err_unit.span(), 8..10);
+
+        assert_eq!(err_label.field(), Some(PerfdataField::Label));
+        assert_eq!(err_label.token(), "'la'bel'");
+        assert_eq!(err_label.span(), 0..8);
+
+        assert!(matches!(
+            err_list.kind(),
+            PerfdataParseErrorKind::ParseValueError(_)
+        ));
+        assert_eq!(err_list.item(), Some(2));
+        assert_eq!(err_list.field(), Some(PerfdataField::Max));
+        assert_eq!(&list[err_list.span()], "1O0");
+        assert_eq!(
+            err_list.to_string(),
+            "perfdata #2: value is not a number in max at 33..36: `1O0`"
+        );
+    }
+
     #[test]
     fn test_parse_omitted() {
         let no_warn = "no_w=10;;30;0;100;";
-        let exp_no_warn = Perfdata::unit("no_w", 10)
+        let exp_no_warn = Perfdata::unitless("no_w", 10)
             .with_crit(ThresholdRange::above_pos(30))
             .with_min(0)
             .with_max(100);
 
         let no_crit = "no_c=10;20;;0;100";
-        let exp_no_crit = Perfdata::unit("no_c", 10)
+        let exp_no_crit = Perfdata::unitless("no_c", 10)
             .with_warn(ThresholdRange::above_pos(20))
             .with_min(0)
             .with_max(100);
 
         let no_min = "no_m=10;20;30;;100";
-        let exp_no_min = Perfdata::unit("no_m", 10)
+        let exp_no_min = Perfdata::unitless("no_m", 10)
             .with_warn(ThresholdRange::above_pos(20))
             .with_crit(ThresholdRange::above_pos(30))
             .with_max(100);
 
         let just_min = "just_m=10;;;0;";
-        let exp_just_min = Perfdata::unit("just_m", 10).with_min(0);
+        let exp_just_min = Perfdata::unitless("just_m", 10).with_min(0);
 
         let got_no_warn = Perfdata::try_from(no_warn).unwrap();
         let got_no_crit = Perfdata::try_from(no_crit).unwrap();
@@ -380,36 +744,132 @@ mod tests {
         let value = 0;
         for unit in Unit::iter() {
             match unit {
-                Unit::None(_) => {
+                Unit::None => {
                     assert_eq!(
                         Perfdata::try_from("test=0").unwrap(),
-                        Perfdata::unit(label, value)
+                        Perfdata::unitless(label, value)
                     )
                 }
-                Unit::Percentage(_) => assert_eq!(
+                Unit::Percentage => assert_eq!(
                     Perfdata::try_from("test=0%").unwrap(),
                     Perfdata::percentage(label, value)
                 ),
-                Unit::Seconds(_) => assert_eq!(
+                Unit::Seconds => assert_eq!(
                     Perfdata::try_from("test=0s").unwrap(),
                     Perfdata::seconds(label, value)
                 ),
-                Unit::Bytes(_) => {
+                Unit::Milliseconds => assert_eq!(
+                    Perfdata::try_from("test=0ms").unwrap(),
+                    Perfdata::milliseconds(label, value)
+                ),
+                Unit::Microseconds => assert_eq!(
+                    Perfdata::try_from("test=0us").unwrap(),
+                    Perfdata::microseconds(label, value)
+                ),
+                Unit::Bytes => {
                     assert_eq!(
                         Perfdata::try_from("test=0b").unwrap(),
                         Perfdata::bytes(label, value)
+                    );
+                    assert_eq!(
+                        Perfdata::try_from("test=0B").unwrap(),
+                        Perfdata::bytes(label, value)
                     )
                 }
-                Unit::Counter(_) => assert_eq!(
+                Unit::Kilobytes => assert_eq!(
+                    Perfdata::try_from("test=0KB").unwrap(),
+                    Perfdata::kilobytes(label, value)
+                ),
+                Unit::Megabytes => assert_eq!(
+                    Perfdata::try_from("test=0MB").unwrap(),
+                    Perfdata::megabytes(label, value)
+                ),
+                Unit::Gigabytes => assert_eq!(
+                    Perfdata::try_from("test=0GB").unwrap(),
+                    Perfdata::gigabytes(label, value)
+                ),
+                Unit::Terabytes => assert_eq!(
+                    Perfdata::try_from("test=0TB").unwrap(),
+                    Perfdata::terabytes(label, value)
+                ),
+                Unit::Kibibytes => assert_eq!(
+                    Perfdata::try_from("test=0KiB").unwrap(),
+                    Perfdata::kibibytes(label, value)
+                ),
+                Unit::Mebibytes => assert_eq!(
+                    Perfdata::try_from("test=0MiB").unwrap(),
+                    Perfdata::mebibytes(label, value)
+                ),
+                Unit::Gibibytes => assert_eq!(
+                    Perfdata::try_from("test=0GiB").unwrap(),
+                    Perfdata::gibibytes(label, value)
+                ),
+                Unit::Tebibytes => assert_eq!(
+                    Perfdata::try_from("test=0TiB").unwrap(),
+                    Perfdata::tebibytes(label, value)
+                ),
+                Unit::Counter => assert_eq!(
                     Perfdata::try_from("test=0c").unwrap(),
                     Perfdata::counter(label, value)
                 ),
-                Unit::Undetermined => assert_eq!(
-                    Perfdata::try_from("test=U").unwrap(),
-                    Perfdata::undetermined(label)
+                Unit::Custom(_) => assert_eq!(
+                    Perfdata::try_from("test=0rpm").unwrap(),
+                    Perfdata::custom(label, value, "rpm").unwrap()
                 ),
             };
         }
+
+        assert_eq!(
+            Perfdata::try_from("test=U").unwrap(),
+            Perfdata::undetermined(label)
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_units() {
+        let list = "fan=1200rpm;;;0 requests=15.5req/s;100;200 temp=21.5°C;30;40 signal=-67dBm";
+
+        let parsed = PerfdataSet::try_from(list).unwrap();
+        let expected: PerfdataSet = vec![
+            Perfdata::custom("fan", 1200, "rpm").unwrap().with_min(0),
+            Perfdata::custom("requests", 15.5, "req/s")
+                .unwrap()
+                .with_warn(ThresholdRange::above_pos(100))
+                .with_crit(ThresholdRange::above_pos(200)),
+            Perfdata::custom("temp", 21.5, "°C")
+                .unwrap()
+                .with_warn(ThresholdRange::above_pos(30))
+                .with_crit(ThresholdRange::above_pos(40)),
+            Perfdata::custom("signal", -67, "dBm").unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(parsed, expected);
+
+        let uoms: Vec<&str> = parsed.data().map(|pd| pd.uom()).collect();
+        assert_eq!(uoms, ["rpm", "req/s", "°C", "dBm"]);
+
+        let formatted = parsed.to_string();
+        assert_eq!(
+            formatted,
+            "'fan'=1200rpm;;;0;; 'requests'=15.5req/s;100;200;;; 'temp'=21.5°C;30;40;;; 'signal'=-67dBm;"
+        );
+        let reparsed = PerfdataSet::try_from(formatted.as_str()).unwrap();
+        assert_eq!(reparsed, expected);
+    }
+
+    #[test]
+    fn test_parse_owned() {
+        let single = String::from("'some perf'=42;@75:80;80");
+        let line = String::from("'some perf'=42;@75:80;80 foo=23s");
+
+        let perfdata = Perfdata::try_from(single).unwrap();
+        let pds = PerfdataSet::try_from(line).unwrap();
+
+        let handle = std::thread::spawn(move || pds.data().count());
+
+        assert_eq!(perfdata.label(), "some perf");
+        assert_eq!(handle.join().unwrap(), 2);
     }
 
     #[test]
@@ -451,16 +911,16 @@ mod tests {
         assert_eq!(exp_inside, got_inside);
 
         assert!(matches!(
-            got_err_number,
-            Err(PerfdataParseError::ParseValueError(_))
+            got_err_number.unwrap_err().kind(),
+            PerfdataParseErrorKind::ParseValueError(_)
         ));
         assert!(matches!(
-            got_err_space,
-            Err(PerfdataParseError::ParseValueError(_))
+            got_err_space.unwrap_err().kind(),
+            PerfdataParseErrorKind::ParseValueError(_)
         ));
         assert!(matches!(
-            got_err_empty,
-            Err(PerfdataParseError::ThresholdEmpty)
+            got_err_empty.unwrap_err().kind(),
+            PerfdataParseErrorKind::ThresholdEmpty
         ));
     }
 
@@ -490,24 +950,63 @@ mod tests {
         assert_eq!(parsed_quoted.label(), exp_quoted);
         assert_eq!(parsed_quoted_with_space.label(), exp_quoted_with_space);
         assert_eq!(
-            parsed_extra_start,
-            Err(PerfdataParseError::LabelContainsSingleQuote)
+            parsed_extra_start.unwrap_err().kind(),
+            &PerfdataParseErrorKind::LabelContainsSingleQuote
         );
         assert_eq!(
-            parsed_extra_end,
-            Err(PerfdataParseError::LabelContainsSingleQuote)
+            parsed_extra_end.unwrap_err().kind(),
+            &PerfdataParseErrorKind::LabelContainsSingleQuote
         );
         assert_eq!(
-            parsed_extra_middle,
-            Err(PerfdataParseError::LabelContainsSingleQuote)
+            parsed_extra_middle.unwrap_err().kind(),
+            &PerfdataParseErrorKind::LabelContainsSingleQuote
         );
-        assert_eq!(parsed_empty, Err(PerfdataParseError::MissingLabel));
-        assert_eq!(parsed_empty_quoted, Err(PerfdataParseError::MissingLabel));
+        assert_eq!(
+            parsed_empty.unwrap_err().kind(),
+            &PerfdataParseErrorKind::MissingLabel
+        );
+        assert_eq!(
+            parsed_empty_quoted.unwrap_err().kind(),
+            &PerfdataParseErrorKind::MissingLabel
+        );
+    }
+
+    #[test]
+    fn test_label_escaped_quotes() {
+        let escaped = "'it''s disk'=10";
+        let only_quote = "''''=1";
+        let unescaped_unquoted = "it''s=1";
+        let odd_quotes = "'it'''s'=1";
+
+        let parsed_escaped = Perfdata::try_from(escaped).unwrap();
+        let parsed_only_quote = Perfdata::try_from(only_quote).unwrap();
+        let parsed_unescaped_unquoted = Perfdata::try_from(unescaped_unquoted);
+        let parsed_odd_quotes = Perfdata::try_from(odd_quotes);
+
+        assert_eq!(parsed_escaped.label(), "it's disk");
+        assert_eq!(parsed_only_quote.label(), "'");
+        assert_eq!(
+            parsed_unescaped_unquoted.unwrap_err().kind(),
+            &PerfdataParseErrorKind::LabelContainsSingleQuote
+        );
+        assert_eq!(
+            parsed_odd_quotes.unwrap_err().kind(),
+            &PerfdataParseErrorKind::LabelContainsSingleQuote
+        );
+
+        let list = "'it''s disk'=10 'owner''s'=5";
+        let parsed_list = PerfdataSet::try_from(list).unwrap();
+        let labels: Vec<&str> = parsed_list.data().map(|pd| pd.label()).collect();
+        assert_eq!(labels, ["it's disk", "owner's"]);
+
+        let formatted = parsed_escaped.to_string();
+        assert_eq!(formatted, "'it''s disk'=10;");
+        assert_eq!(Perfdata::try_from(&formatted).unwrap(), parsed_escaped);
     }
 
     #[test]
     fn test_format_and_parse_back() {
-        let simple = Perfdata::unit("simple", 10);
+        let simple = Perfdata::unitless("simple", 10);
         let full = Perfdata::bytes("full", 10)
             .with_warn(ThresholdRange::above_pos(20))
             .with_crit(ThresholdRange::above_pos(30))
@@ -523,4 +1022,37 @@ mod tests {
         assert_eq!(simple, parsed_simple);
         assert_eq!(full, parsed_full);
     }
+
+    #[test]
+    fn test_parse_prefixed_units() {
+        let list = "time=0.123s;1;2;0 rta=12.5ms;100;500;0 jitter=350us used=1234MB;2000;3000;0;4096 free=7GiB size=512B";
+
+        let parsed = PerfdataSet::try_from(list).unwrap();
+        let expected: PerfdataSet = vec![
+            Perfdata::seconds("time", 0.123)
+                .with_warn(ThresholdRange::above_pos(1))
+                .with_crit(ThresholdRange::above_pos(2))
+                .with_min(0),
+            Perfdata::milliseconds("rta", 12.5)
+                .with_warn(ThresholdRange::above_pos(100))
+                .with_crit(ThresholdRange::above_pos(500))
+                .with_min(0),
+            Perfdata::microseconds("jitter", 350),
+            Perfdata::megabytes("used", 1234)
+                .with_warn(ThresholdRange::above_pos(2000))
+                .with_crit(ThresholdRange::above_pos(3000))
+                .with_min(0)
+                .with_max(4096),
+            Perfdata::gibibytes("free", 7),
+            Perfdata::bytes("size", 512),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(parsed, expected);
+
+        let formatted = parsed.to_string();
+        assert!(formatted.ends_with("'size'=512B;"));
+        let reparsed = PerfdataSet::try_from(formatted.as_str()).unwrap();
+        assert_eq!(reparsed, expected);
+    }
 }
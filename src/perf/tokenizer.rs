@@ -0,0 +1,116 @@
+// Perfdata items are delimited by whitespace, but quoted labels may contain whitespace and equals
+// signs. For compatibility unquoted labels may contain spaces as well, so whitespace only ends an
+// item after the equals sign delimiting the label from the data.
+
+use crate::perf::parser::{LABEL_DELIMITER, LABEL_QUOTE};
+
+/// Iterator over the perfdata items in a whitespace delimited list, yielding the byte offset where
+/// each item began in the input together with the item itself.
+pub(crate) struct Tokens<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+pub(crate) fn tokenize(input: &str) -> Tokens<'_> {
+    Tokens { input, offset: 0 }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remainder = &self.input[self.offset..];
+        let trimmed = remainder.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        let start = self.offset + remainder.len() - trimmed.len();
+
+        if trimmed.is_empty() {
+            self.offset = start;
+            return None;
+        }
+
+        let end = token_end(trimmed);
+        self.offset = start + end;
+        Some((start, &trimmed[..end]))
+    }
+}
+
+fn token_end(token: &str) -> usize {
+    let find_whitespace = |s: &str| s.find(|c: char| c.is_ascii_whitespace());
+
+    match find_label_delimiter(token) {
+        Some(idx) => {
+            let data_start = idx + LABEL_DELIMITER.len_utf8();
+            find_whitespace(&token[data_start..])
+                .map(|end| data_start + end)
+                .unwrap_or(token.len())
+        }
+        // Without equals sign this can't be valid perfdata, the item is kept as short as possible
+        None => find_whitespace(token).unwrap_or(token.len()),
+    }
+}
+
+/// Returns the index of the first equals sign, which is not part of a quoted label.
+/// Escaped quotes (`''`) toggle the quoting twice, and as such need no special handling.
+pub(crate) fn find_label_delimiter(s: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (idx, c) in s.char_indices() {
+        match c {
+            LABEL_QUOTE => quoted = !quoted,
+            LABEL_DELIMITER if !quoted => return Some(idx),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let input = " a=1;2;3\tb=2s\n\r'c d'=3  'e=f'=4 'g'' h'=5 ";
+
+        let tokens: Vec<(usize, &str)> = tokenize(input).collect();
+
+        assert_eq!(
+            tokens,
+            [
+                (1, "a=1;2;3"),
+                (9, "b=2s"),
+                (15, "'c d'=3"),
+                (24, "'e=f'=4"),
+                (32, "'g'' h'=5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_unquoted_spaces() {
+        let input = "with space=1 other=2";
+
+        let tokens: Vec<(usize, &str)> = tokenize(input).collect();
+
+        assert_eq!(tokens, [(0, "with space=1"), (13, "other=2")]);
+    }
+
+    #[test]
+    fn test_tokenize_without_delimiter() {
+        let input = "a=1 trailing garbage";
+        let empty = " \t\n";
+
+        let tokens: Vec<(usize, &str)> = tokenize(input).collect();
+
+        assert_eq!(tokens, [(0, "a=1"), (4, "trailing"), (13, "garbage")]);
+        assert_eq!(tokenize(empty).count(), 0);
+    }
+
+    #[test]
+    fn test_find_label_delimiter() {
+        assert_eq!(find_label_delimiter("a=1"), Some(1));
+        assert_eq!(find_label_delimiter("'a=b'=1"), Some(5));
+        assert_eq!(find_label_delimiter("'a''=b'=1"), Some(7));
+        assert_eq!(find_label_delimiter("'a=1"), None);
+        assert_eq!(find_label_delimiter("a"), None);
+    }
+}
@@ -0,0 +1,394 @@
+use crate::error::{offset_of, PerfdataParseError};
+use crate::{NumberFormat, Perfdata, PerfdataSet};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+
+// Source: https://nagios-plugins.org/doc/guidelines.html#AEN33
+// This is the expected format:
+// TEXT OUTPUT | OPTIONAL PERFDATA
+// LONG TEXT LINE 1
+// LONG TEXT LINE 2
+// ...
+// LONG TEXT LINE N | PERFDATA LINE 2
+// PERFDATA LINE 3
+// PERFDATA LINE N
+// Notes:
+//     only the first line is required, perfdata is optional on both the first line and the last
+//     long text line. All lines following the second perfdata separator are perfdata.
+//     when formatted, the output is normalized: all perfdata is written on the first line. The
+//     formatted output is not necessarily the same as the parsed output, but parses back into the
+//     same `PluginOutput`, except for empty lines at the end of the long text.
+//     the separator can't be escaped, so text given to the builders is normalized the same way as
+//     it would be parsed: a `|` is replaced with a broken bar `¦`, text with several lines is split
+//     into lines of long text, and trailing whitespace of the summary text is removed.
+
+const PERFDATA_SEPARATOR: char = '|';
+const SEPARATOR_REPLACEMENT: &str = "¦";
+
+/// `PluginOutput` represents the complete output of a monitoring check as described in the
+/// [Nagios Plugin API](https://nagios-plugins.org/doc/guidelines.html#AEN33).
+///
+/// It consists of a summary `text` on the first line, optional `long_text` lines, and
+/// [Perfdata](`crate::Perfdata`) which may be spread across several lines in the output, but is
+/// merged into a single [PerfdataSet].
+///
+/// When formatted, all perfdata is written on the first line, followed by the long text lines. For
+/// output with perfdata on the long text lines, this is a different but equivalent wire format,
+/// which parses back into the same `PluginOutput`.
+///
+/// As `|` would start the perfdata, it is replaced with `¦` in text passed to the builders. Text
+/// with several lines is split into lines of long text.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, PartialEq)]
+pub struct PluginOutput<'a> {
+    text: Cow<'a, str>,
+    long_text: Vec<Cow<'a, str>>,
+    perfdata: PerfdataSet<'a>,
+}
+
+impl<'a> PluginOutput<'a> {
+    /// Create a new `PluginOutput` with the given summary text. Any lines after the first one are
+    /// added as long text.
+    pub fn new<T: Into<Cow<'a, str>>>(text: T) -> Self {
+        let mut lines = split_lines(text.into()).into_iter();
+        PluginOutput {
+            text: lines.next().map(trim_end).unwrap_or_default(),
+            long_text: lines.collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a line of long text to the `PluginOutput`
+    #[must_use]
+    pub fn with_long_text<T: Into<Cow<'a, str>>>(mut self, line: T) -> Self {
+        self.add_long_text(line);
+        self
+    }
+
+    /// Add a line of long text to the `PluginOutput`
+    pub fn add_long_text<T: Into<Cow<'a, str>>>(&mut self, line: T) {
+        self.long_text.extend(split_lines(line.into()));
+    }
+
+    /// Add [Perfdata] to the `PluginOutput`
+    pub fn add_perfdata(&mut self, pd: Perfdata<'a>) {
+        self.perfdata.add(pd);
+    }
+
+    /// Set the [PerfdataSet] of the `PluginOutput`
+    #[must_use]
+    pub fn with_perfdata(mut self, perfdata: PerfdataSet<'a>) -> Self {
+        self.perfdata = perfdata;
+        self
+    }
+
+    /// The summary text, which is the first line of the output without perfdata
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns an iterator over all lines of long text
+    pub fn long_text(&self) -> impl Iterator<Item = &str> {
+        self.long_text.iter().map(|line| line.as_ref())
+    }
+
+    /// The merged [PerfdataSet] of all lines
+    pub fn perfdata(&self) -> &PerfdataSet<'a> {
+        &self.perfdata
+    }
+
+    /// Converts the `PluginOutput` into one which owns all of its data, and as such is no longer
+    /// bound to the lifetime of the input it was parsed from
+    pub fn into_owned(self) -> PluginOutput<'static> {
+        PluginOutput {
+            text: Cow::Owned(self.text.into_owned()),
+            long_text: self
+                .long_text
+                .into_iter()
+                .map(|line| Cow::Owned(line.into_owned()))
+                .collect(),
+            perfdata: self.perfdata.into_owned(),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for PluginOutput<'a> {
+    type Error = PerfdataParseError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let mut lines = value.lines();
+        let mut perfdata = Vec::new();
+
+        // The first line contains the summary text, and optionally perfdata
+        let first_line = lines.next().unwrap_or_default();
+        let text = match first_line.split_once(PERFDATA_SEPARATOR) {
+            Some((text, data)) => {
+                perfdata.push(data);
+                text
+            }
+            None => first_line,
+        };
+
+        // Long text follows until the next separator, after which only perfdata lines follow
+        let mut long_text = Vec::new();
+        for line in lines.by_ref() {
+            match line.split_once(PERFDATA_SEPARATOR) {
+                Some((text, data)) => {
+                    let text = text.trim_end();
+                    if !text.is_empty() {
+                        long_text.push(Cow::Borrowed(text));
+                    }
+                    perfdata.push(data);
+                    break;
+                }
+                None => long_text.push(Cow::Borrowed(line)),
+            }
+        }
+        perfdata.extend(lines);
+
+        // Items are counted across all lines, which all parsed up to the failing one
+        let mut merged = PerfdataSet::new();
+        for data in perfdata {
+            let parsed = PerfdataSet::try_from(data).map_err(|e| {
+                e.with_offset(offset_of(value, data).unwrap_or_default())
+                    .with_item(merged.len())
+            })?;
+            merged.extend(parsed);
+        }
+
+        Ok(PluginOutput {
+            text: Cow::Borrowed(text.trim_end()),
+            long_text,
+            perfdata: merged,
+        })
+    }
+}
+
+// Splits text into lines, and replaces the separator which would start the perfdata
+fn split_lines(text: Cow<'_, str>) -> Vec<Cow<'_, str>> {
+    match text {
+        _ if !text.contains('\n') => vec![escape_separator(text)],
+        Cow::Borrowed(text) => text
+            .lines()
+            .map(|line| escape_separator(Cow::Borrowed(line)))
+            .collect(),
+        Cow::Owned(text) => text
+            .lines()
+            .map(|line| escape_separator(Cow::Owned(line.to_string())))
+            .collect(),
+    }
+}
+
+fn escape_separator(line: Cow<'_, str>) -> Cow<'_, str> {
+    if line.contains(PERFDATA_SEPARATOR) {
+        Cow::Owned(line.replace(PERFDATA_SEPARATOR, SEPARATOR_REPLACEMENT))
+    } else {
+        line
+    }
+}
+
+// The summary text is trimmed when parsed
+fn trim_end(text: Cow<'_, str>) -> Cow<'_, str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(text.trim_end()),
+        Cow::Owned(text) if text.ends_with(char::is_whitespace) => {
+            Cow::Owned(text.trim_end().to_string())
+        }
+        text => text,
+    }
+}
+
+impl<'a> TryFrom<&'a String> for PluginOutput<'a> {
+    type Error = PerfdataParseError;
+
+    fn try_from(value: &'a String) -> Result<Self, Self::Error> {
+        PluginOutput::try_from(value.as_str())
+    }
+}
+
+impl TryFrom<String> for PluginOutput<'static> {
+    type Error = PerfdataParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        PluginOutput::try_from(value.as_str()).map(PluginOutput::into_owned)
+    }
+}
+
+impl PluginOutput<'_> {
+    pub(crate) fn fmt_with(
+        &self,
+        f: &mut Formatter<'_>,
+        format: &NumberFormat,
+    ) -> std::fmt::Result {
+        write!(f, "{}", self.text)?;
+        if !self.perfdata.is_empty() {
+            if !self.text.is_empty() {
+                write!(f, " ")?;
+            }
+            write!(
+                f,
+                "{} {}",
+                PERFDATA_SEPARATOR,
+                self.perfdata.display_with(*format)
+            )?;
+        }
+        for line in &self.long_text {
+            write!(f, "\n{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for PluginOutput<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with(f, &NumberFormat::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PerfdataParseErrorKind;
+    use crate::ThresholdRange;
+
+    #[test]
+    fn test_parse_text_only() {
+        let output = "OK - everything is fine";
+
+        let parsed = PluginOutput::try_from(output).unwrap();
+
+        assert_eq!(parsed.text(), "OK - everything is fine");
+        assert_eq!(parsed.long_text().count(), 0);
+        assert!(parsed.perfdata().is_empty());
+        assert_eq!(parsed.to_string(), output);
+    }
+
+    #[test]
+    fn test_parse_single_line() {
+        let output = "DISK OK - free space: / 3326 MB (56%); | /=2643MB;5948;5958;0;5968";
+
+        let parsed = PluginOutput::try_from(output).unwrap();
+        let expected: PerfdataSet = vec![Perfdata::megabytes("/", 2643)
+            .with_warn(ThresholdRange::above_pos(5948))
+            .with_crit(ThresholdRange::above_pos(5958))
+            .with_min(0)
+            .with_max(5968)]
+        .into();
+
+        assert_eq!(parsed.text(), "DISK OK - free space: / 3326 MB (56%);");
+        assert_eq!(parsed.long_text().count(), 0);
+        assert_eq!(parsed.perfdata(), &expected);
+    }
+
+    #[test]
+    fn test_parse_multiple_lines() {
+        let output = "DISK OK - free space: / 3326 MB (56%); | /=2643MB;5948;5958;0;5968
+/ 15272 MB (77%);
+/boot 68 MB (69%);
+/home 69357 MB (27%);
+/var/log 819 MB (84%); | /boot=68MB;88;93;0;98
+/home=69357MB;253404;253409;0;253414
+/var/log=818MB;970;975;0;980
+";
+
+        let parsed = PluginOutput::try_from(output).unwrap();
+        let labels: Vec<&str> = parsed.perfdata().data().map(|pd| pd.label()).collect();
+        let long_text: Vec<&str> = parsed.long_text().collect();
+
+        assert_eq!(parsed.text(), "DISK OK - free space: / 3326 MB (56%);");
+        assert_eq!(
+            long_text,
+            [
+                "/ 15272 MB (77%);",
+                "/boot 68 MB (69%);",
+                "/home 69357 MB (27%);",
+                "/var/log 819 MB (84%);"
+            ]
+        );
+        assert_eq!(labels, ["/", "/boot", "/home", "/var/log"]);
+
+        let formatted = parsed.to_string();
+        assert!(formatted.starts_with(
+            "DISK OK - free space: / 3326 MB (56%); | '/'=2643MB;5948;5958;0;5968; '/boot'=68MB;"
+        ));
+        assert!(formatted.ends_with("\n/var/log 819 MB (84%);"));
+        assert_eq!(PluginOutput::try_from(formatted.as_str()).unwrap(), parsed);
+    }
+
+    #[test]
+    fn test_parse_long_text_without_perfdata() {
+        let output = "OK - all good\nfirst detail\n\nsecond detail";
+
+        let parsed = PluginOutput::try_from(output).unwrap();
+        let long_text: Vec<&str> = parsed.long_text().collect();
+
+        assert_eq!(long_text, ["first detail", "", "second detail"]);
+        assert!(parsed.perfdata().is_empty());
+        assert_eq!(parsed.to_string(), output);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let output = "OK | foo=1 bar=2\nlong text | baz=1x2";
+
+        let err = PluginOutput::try_from(output).unwrap_err();
+
+        assert!(matches!(err.kind(), PerfdataParseErrorKind::InvalidUnit(_)));
+        assert_eq!(err.item(), Some(2));
+        assert_eq!(&output[err.span()], "x2");
+
+        let garbage = "OK | a=1 junk b=2\nlong text | c=3\nd=1x2";
+        let err = PluginOutput::try_from(garbage).unwrap_err();
+        assert_eq!(err.item(), Some(3));
+        assert_eq!(&garbage[err.span()], "x2");
+    }
+
+    #[test]
+    fn test_format_separator_in_text() {
+        let output = PluginOutput::new("in|out ok ")
+            .with_long_text("a|b")
+            .with_long_text(String::from("c\nd|e"))
+            .with_perfdata(vec![Perfdata::unitless("a", 1)].into());
+
+        let formatted = output.to_string();
+
+        assert_eq!(formatted, "in¦out ok | 'a'=1;\na¦b\nc\nd¦e");
+        assert_eq!(PluginOutput::try_from(formatted).unwrap(), output);
+        assert_eq!(PluginOutput::new("OK\ndetail").long_text().count(), 1);
+    }
+
+    #[test]
+    fn test_format_without_text() {
+        let output = PluginOutput::new("").with_perfdata(vec![Perfdata::unitless("a", 1)].into());
+
+        let formatted = output.to_string();
+
+        assert_eq!(formatted, "| 'a'=1;");
+        assert_eq!(PluginOutput::try_from(formatted).unwrap(), output);
+    }
+
+    #[test]
+    fn test_format_and_parse_back() {
+        let output = PluginOutput::new("CRITICAL - load too high")
+            .with_long_text("load1 is 12")
+            .with_long_text(format!("load5 is {}", 8))
+            .with_perfdata(
+                vec![
+                    Perfdata::unitless("load1", 12).with_crit(ThresholdRange::above_pos(10)),
+                    Perfdata::unitless("load5", 8).with_crit(ThresholdRange::above_pos(10)),
+                ]
+                .into(),
+            );
+
+        let formatted = output.to_string();
+        let parsed = PluginOutput::try_from(formatted.clone()).unwrap();
+
+        assert_eq!(
+            formatted,
+            "CRITICAL - load too high | 'load1'=12;;10;;; 'load5'=8;;10;;;\nload1 is 12\nload5 is 8"
+        );
+        assert_eq!(parsed, output);
+    }
+}
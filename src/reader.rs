@@ -0,0 +1,185 @@
+use crate::error::PerfdataReadError;
+use crate::{ParseOptions, Perfdata, PerfdataSet};
+use std::io::BufRead;
+
+/// Reads lines into a reused buffer, and keeps track of the current line number
+pub(crate) struct LineReader<R> {
+    reader: R,
+    buffer: String,
+    line: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        LineReader {
+            reader,
+            buffer: String::new(),
+            line: 0,
+        }
+    }
+
+    // Returns the next line without line ending together with its line number, None at the end
+    pub(crate) fn next_line(&mut self) -> Option<Result<(usize, &str), PerfdataReadError>> {
+        self.buffer.clear();
+        self.line += 1;
+        match self.reader.read_line(&mut self.buffer) {
+            Ok(0) => None,
+            Ok(_) => {
+                let line = self.buffer.trim_end_matches(['\n', '\r']);
+                Some(Ok((self.line, line)))
+            }
+            Err(source) => Some(Err(PerfdataReadError::Io {
+                line: self.line,
+                source,
+            })),
+        }
+    }
+}
+
+/// `PerfdataReader` parses perfdata from any [BufRead], e.g. a file containing one list of
+/// perfdata per line. Only a single line is kept in memory at once.
+///
+/// As an [Iterator] it yields one [PerfdataSet] per line, skipping empty lines. Errors contain
+/// the line number and do not end the iteration. Single [Perfdata] items can be read with
+/// [into_items()](`Self::into_items()`).
+pub struct PerfdataReader<R> {
+    lines: LineReader<R>,
+    options: ParseOptions,
+}
+
+impl<R: BufRead> PerfdataReader<R> {
+    /// Create a new `PerfdataReader` reading from the given [BufRead]
+    pub fn new(reader: R) -> Self {
+        PerfdataReader {
+            lines: LineReader::new(reader),
+            options: ParseOptions::default(),
+        }
+    }
+
+    /// Use the given [ParseOptions] for parsing each line
+    #[must_use]
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns an iterator over the single [Perfdata] items of all lines
+    pub fn into_items(self) -> PerfdataItems<R> {
+        PerfdataItems {
+            reader: self,
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PerfdataReader<R> {
+    type Item = Result<PerfdataSet<'static>, PerfdataReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (line_number, line) = match self.lines.next_line()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let parsed = PerfdataSet::parse_with(line, &self.options)
+                .map(PerfdataSet::into_owned)
+                .map_err(|source| PerfdataReadError::Parse {
+                    line: line_number,
+                    source,
+                });
+            return Some(parsed);
+        }
+    }
+}
+
+/// Iterator over the single [Perfdata] items read by a [PerfdataReader], see
+/// [PerfdataReader::into_items()]
+pub struct PerfdataItems<R> {
+    reader: PerfdataReader<R>,
+    current: std::vec::IntoIter<Perfdata<'static>>,
+}
+
+impl<R: BufRead> Iterator for PerfdataItems<R> {
+    type Item = Result<Perfdata<'static>, PerfdataReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pd) = self.current.next() {
+                return Some(Ok(pd));
+            }
+
+            match self.reader.next()? {
+                Ok(pds) => self.current = pds.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PerfdataParseErrorKind;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_lines() {
+        let input = "a=1 b=2s\r\n\nc=3%\n  \nd=4;5;6\n";
+
+        let sets: Vec<PerfdataSet> = PerfdataReader::new(Cursor::new(input))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let counts: Vec<usize> = sets.iter().map(|pds| pds.len()).collect();
+
+        assert_eq!(counts, [2, 1, 1]);
+        assert_eq!(sets[1], vec![Perfdata::percentage("c", 3)].into());
+    }
+
+    #[test]
+    fn test_read_items() {
+        let input = "a=1 b=2s\nc=3%\nd=4;5;6";
+
+        let labels: Vec<String> = PerfdataReader::new(Cursor::new(input))
+            .into_items()
+            .map(|pd| pd.unwrap().label().to_string())
+            .collect();
+
+        assert_eq!(labels, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let input = "a=1\nb=x\n\nc=3 d\ne=1,5";
+        let options = ParseOptions::new()
+            .with_decimal_comma(true)
+            .with_trailing_garbage(false);
+
+        let results: Vec<_> = PerfdataReader::new(Cursor::new(input))
+            .with_options(options)
+            .collect();
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok());
+        assert!(results[3].is_ok());
+
+        let err_value = results[1].as_ref().unwrap_err();
+        let err_garbage = results[2].as_ref().unwrap_err();
+
+        assert_eq!(err_value.line(), 2);
+        assert_eq!(err_garbage.line(), 4);
+        assert!(matches!(
+            err_garbage,
+            PerfdataReadError::Parse { source, .. }
+                if source.kind() == &PerfdataParseErrorKind::MissingEqualsSign
+        ));
+        assert_eq!(
+            err_value.to_string(),
+            "line 2: perfdata #0: value is not a number in value at 2..3: `x`"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Alternative serde representations, to be used with `#[serde(with = "...")]` on fields.
+//!
+//! ```text
+//! #[derive(Serialize, Deserialize)]
+//! struct CachedResult {
+//!     #[serde(with = "perfdata::serde_as::exit_code")]
+//!     status: MonitoringStatus,
+//!     #[serde(with = "perfdata::serde_as::nagios")]
+//!     perfdata: PerfdataSet<'static>,
+//!     #[serde(with = "perfdata::serde_as::nagios::option")]
+//!     warn: Option<ThresholdRange>,
+//! }
+//! ```
+
+use crate::{Perfdata, PerfdataSet, ThresholdRange};
+use std::fmt::Display;
+
+/// Types which can be represented in the string form of the
+/// [Nagios Reference](https://nagios-plugins.org/doc/guidelines.html#AEN200), e.g. `@10:20`
+pub trait NagiosFormat: Display + Sized {
+    /// Parses the value from its string form
+    fn from_nagios(s: &str) -> Result<Self, String>;
+}
+
+impl NagiosFormat for ThresholdRange {
+    fn from_nagios(s: &str) -> Result<Self, String> {
+        s.parse().map_err(|e| format!("{}", e))
+    }
+}
+
+impl NagiosFormat for Perfdata<'_> {
+    fn from_nagios(s: &str) -> Result<Self, String> {
+        Perfdata::try_from(s)
+            .map(Perfdata::into_owned)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl NagiosFormat for PerfdataSet<'_> {
+    fn from_nagios(s: &str) -> Result<Self, String> {
+        PerfdataSet::try_from(s)
+            .map(PerfdataSet::into_owned)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Serializes a [MonitoringStatus](`crate::MonitoringStatus`) as its exit code. Deserialization
+/// accepts both exit codes and names in human readable formats like JSON, and only exit codes
+/// otherwise.
+///
+/// Unlike `From<i32>`, which maps any exit code of a plugin
+/// other than 0 to 2 to [Unknown](`crate::MonitoringStatus::Unknown`) as the plugin guidelines
+/// require, exit codes other than 0 to 3 are rejected. They are never serialized, so they can only
+/// stem from corrupted or foreign data.
+pub mod exit_code {
+    use crate::monitoring_status::StatusVisitor;
+    use crate::MonitoringStatus;
+    use serde::{Deserializer, Serializer};
+
+    /// Serializes the status as its exit code
+    pub fn serialize<S: Serializer>(
+        status: &MonitoringStatus,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(status.exit_code())
+    }
+
+    /// Deserializes the status from its exit code, or from its name in human readable formats
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MonitoringStatus, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(StatusVisitor)
+        } else {
+            deserializer.deserialize_i32(StatusVisitor)
+        }
+    }
+}
+
+/// Serializes a [NagiosFormat] type, i.e. [Perfdata], [PerfdataSet] or [ThresholdRange], in its
+/// string form like `'label'=10ms;@10:20`
+pub mod nagios {
+    use super::NagiosFormat;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serializes the value in its string form
+    pub fn serialize<T: NagiosFormat, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    /// Deserializes the value from its string form
+    pub fn deserialize<'de, T: NagiosFormat, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        T::from_nagios(&s).map_err(D::Error::custom)
+    }
+
+    /// Serializes an optional [NagiosFormat] type in its string form, or as none
+    pub mod option {
+        use super::NagiosFormat;
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        /// Serializes the value in its string form, if present
+        pub fn serialize<T: NagiosFormat, S: Serializer>(
+            value: &Option<T>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => serializer.collect_str(value),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        /// Deserializes the value from its string form, if present
+        pub fn deserialize<'de, T: NagiosFormat, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<T>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|s| T::from_nagios(&s).map_err(D::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MonitoringStatus, Perfdata, PerfdataSet, ThresholdRange};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Cached {
+        #[serde(with = "crate::serde_as::exit_code")]
+        status: MonitoringStatus,
+        #[serde(with = "crate::serde_as::nagios")]
+        perfdata: PerfdataSet<'static>,
+        #[serde(with = "crate::serde_as::nagios")]
+        single: Perfdata<'static>,
+        #[serde(with = "crate::serde_as::nagios::option")]
+        warn: Option<ThresholdRange>,
+        #[serde(with = "crate::serde_as::nagios::option")]
+        crit: Option<ThresholdRange>,
+    }
+
+    #[test]
+    fn test_string_forms() {
+        let cached = Cached {
+            status: MonitoringStatus::Critical,
+            perfdata: vec![Perfdata::unitless("a", 1), Perfdata::seconds("b", 2)].into(),
+            single: Perfdata::milliseconds("rta", 12),
+            warn: Some(ThresholdRange::inside(10, 20)),
+            crit: None,
+        };
+
+        let json = serde_json::to_value(&cached).unwrap();
+        let parsed: Cached = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "status": 2,
+                "perfdata": "'a'=1; 'b'=2s;",
+                "single": "'rta'=12ms;",
+                "warn": "@10:20",
+                "crit": null,
+            })
+        );
+        assert_eq!(parsed, cached);
+    }
+
+    #[test]
+    fn test_invalid_string_forms() {
+        let json =
+            json!({"status": 5, "perfdata": "", "single": "a=1", "warn": null, "crit": null});
+        let threshold =
+            json!({"status": 0, "perfdata": "", "single": "a=1", "warn": "x", "crit": null});
+
+        assert!(serde_json::from_value::<Cached>(json).is_err());
+        assert!(serde_json::from_value::<Cached>(threshold).is_err());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Status(#[serde(with = "crate::serde_as::exit_code")] MonitoringStatus);
+
+        let named: Vec<Status> = serde_json::from_str(r#"[1, "critical"]"#).unwrap();
+        let bytes = bincode::serialize(&named).unwrap();
+
+        assert_eq!(
+            named,
+            [
+                Status(MonitoringStatus::Warning),
+                Status(MonitoringStatus::Critical)
+            ]
+        );
+        assert_eq!(bincode::deserialize::<Vec<Status>>(&bytes).unwrap(), named);
+        assert!(serde_json::from_str::<Status>("4").is_err());
+    }
+}
@@ -0,0 +1,488 @@
+use crate::error::{offset_of, PerfdataReadError, PerfdataRecordError};
+use crate::reader::LineReader;
+use crate::{HostStatus, MonitoringStatus, ParseOptions, PerfdataSet};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, Write};
+
+// Source: https://docs.pnp4nagios.org/pnp-0.6/config#bulk_mode
+// Nagios writes one record per line, using templates like these:
+// DATATYPE::SERVICEPERFDATA\tTIMET::$TIMET$\tHOSTNAME::$HOSTNAME$\tSERVICEDESC::$SERVICEDESC$\t
+//     SERVICEPERFDATA::$SERVICEPERFDATA$\tSERVICECHECKCOMMAND::$SERVICECHECKCOMMAND$\t
+//     SERVICESTATE::$SERVICESTATE$\tSERVICESTATETYPE::$SERVICESTATETYPE$
+// DATATYPE::HOSTPERFDATA\tTIMET::$TIMET$\tHOSTNAME::$HOSTNAME$\tHOSTPERFDATA::$HOSTPERFDATA$\t
+//     HOSTCHECKCOMMAND::$HOSTCHECKCOMMAND$\tHOSTSTATE::$HOSTSTATE$\tHOSTSTATETYPE::$HOSTSTATETYPE$
+// Notes:
+//     fields may appear in any order, unknown fields are ignored. Fields of the host are part of
+//     service records as well, but only those of the service are kept for them.
+
+const FIELD_DELIMITER: char = '\t';
+const KEY_DELIMITER: &str = "::";
+
+/// The kind of monitoring object a [PerfdataRecord] belongs to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecordKind {
+    /// A host check, with `DATATYPE::HOSTPERFDATA`
+    Host,
+    /// A service check, with `DATATYPE::SERVICEPERFDATA`
+    Service,
+}
+
+impl RecordKind {
+    fn data_type(&self) -> &'static str {
+        match self {
+            RecordKind::Host => "HOSTPERFDATA",
+            RecordKind::Service => "SERVICEPERFDATA",
+        }
+    }
+
+    // The prefix of the object specific keys, e.g. `SERVICESTATE`
+    fn prefix(&self) -> &'static str {
+        match self {
+            RecordKind::Host => "HOST",
+            RecordKind::Service => "SERVICE",
+        }
+    }
+}
+
+/// `PerfdataRecord` is a single line of a `host-perfdata-file` or `service-perfdata-file`
+/// written by Nagios or Icinga, in the format used by the bulk mode of PNP4Nagios.
+///
+/// The `state` and `state_type` are those of the host for host records and those of the service
+/// for service records, and are kept as written, e.g. `CRITICAL` and `HARD`.
+#[derive(Debug, PartialEq)]
+pub struct PerfdataRecord<'a> {
+    kind: RecordKind,
+    timestamp: i64,
+    host: Cow<'a, str>,
+    service: Option<Cow<'a, str>>,
+    check_command: Option<Cow<'a, str>>,
+    state: Option<Cow<'a, str>>,
+    state_type: Option<Cow<'a, str>>,
+    perfdata: PerfdataSet<'a>,
+}
+
+impl<'a> PerfdataRecord<'a> {
+    /// Create a new `PerfdataRecord` for a host check at the given unix timestamp
+    pub fn host<T: Into<Cow<'a, str>>>(timestamp: i64, host: T) -> Self {
+        PerfdataRecord {
+            kind: RecordKind::Host,
+            timestamp,
+            host: host.into(),
+            service: None,
+            check_command: None,
+            state: None,
+            state_type: None,
+            perfdata: PerfdataSet::new(),
+        }
+    }
+
+    /// Create a new `PerfdataRecord` for a service check at the given unix timestamp
+    pub fn service<H, S>(timestamp: i64, host: H, service: S) -> Self
+    where
+        H: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        PerfdataRecord {
+            kind: RecordKind::Service,
+            service: Some(service.into()),
+            ..Self::host(timestamp, host)
+        }
+    }
+
+    /// Set the check command of the `PerfdataRecord`
+    #[must_use]
+    pub fn with_check_command<T: Into<Cow<'a, str>>>(mut self, check_command: T) -> Self {
+        self.check_command = Some(check_command.into());
+        self
+    }
+
+    /// Set the state of the `PerfdataRecord`, e.g. `OK` or `UP`
+    #[must_use]
+    pub fn with_state<T: Into<Cow<'a, str>>>(mut self, state: T) -> Self {
+        self.state = Some(state.into());
+        self
+    }
+
+    /// Set the state type of the `PerfdataRecord`, e.g. `HARD` or `SOFT`
+    #[must_use]
+    pub fn with_state_type<T: Into<Cow<'a, str>>>(mut self, state_type: T) -> Self {
+        self.state_type = Some(state_type.into());
+        self
+    }
+
+    /// Set the [PerfdataSet] of the `PerfdataRecord`
+    #[must_use]
+    pub fn with_perfdata(mut self, perfdata: PerfdataSet<'a>) -> Self {
+        self.perfdata = perfdata;
+        self
+    }
+
+    /// Whether this is a host or a service record
+    pub fn kind(&self) -> RecordKind {
+        self.kind
+    }
+
+    /// The unix timestamp of the check
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// The name of the host
+    pub fn host_name(&self) -> &str {
+        &self.host
+    }
+
+    /// The description of the service, only present for service records
+    pub fn service_description(&self) -> Option<&str> {
+        self.service.as_deref()
+    }
+
+    /// The check command, if present
+    pub fn check_command(&self) -> Option<&str> {
+        self.check_command.as_deref()
+    }
+
+    /// The state of the host or service, if present
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    /// The state of a service record as [MonitoringStatus], if present and valid. Always `None`
+    /// for host records.
+    pub fn service_status(&self) -> Option<MonitoringStatus> {
+        match self.kind {
+            RecordKind::Service => self.state()?.parse().ok(),
+            RecordKind::Host => None,
+        }
+    }
+
+    /// The state of a host record as [HostStatus], if present and valid. Always `None` for service
+    /// records.
+    pub fn host_status(&self) -> Option<HostStatus> {
+        match self.kind {
+            RecordKind::Host => self.state()?.parse().ok(),
+            RecordKind::Service => None,
+        }
+    }
+
+    /// The state type of the host or service, if present
+    pub fn state_type(&self) -> Option<&str> {
+        self.state_type.as_deref()
+    }
+
+    /// The [PerfdataSet] of the check
+    pub fn perfdata(&self) -> &PerfdataSet<'a> {
+        &self.perfdata
+    }
+
+    /// Converts the `PerfdataRecord` into one which owns all of its data, and as such is no longer
+    /// bound to the lifetime of the input it was parsed from
+    pub fn into_owned(self) -> PerfdataRecord<'static> {
+        let owned = |s: Cow<'_, str>| Cow::Owned(s.into_owned());
+        PerfdataRecord {
+            kind: self.kind,
+            timestamp: self.timestamp,
+            host: owned(self.host),
+            service: self.service.map(owned),
+            check_command: self.check_command.map(owned),
+            state: self.state.map(owned),
+            state_type: self.state_type.map(owned),
+            perfdata: self.perfdata.into_owned(),
+        }
+    }
+
+    /// Parse a single line of a perfdata file, using the given [ParseOptions] for its perfdata
+    pub fn parse_with(value: &'a str, options: &ParseOptions) -> Result<Self, PerfdataRecordError> {
+        let mut fields = Vec::new();
+        for field in value.split(FIELD_DELIMITER) {
+            match field.split_once(KEY_DELIMITER) {
+                Some(pair) => fields.push(pair),
+                None if field.trim().is_empty() => {}
+                None => return Err(PerfdataRecordError::InvalidField(field.to_string())),
+            }
+        }
+
+        let get = |key: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| *v)
+                .filter(|v| !v.is_empty())
+        };
+        let require = |key: &'static str| get(key).ok_or(PerfdataRecordError::MissingField(key));
+
+        let kind = match require("DATATYPE")? {
+            "HOSTPERFDATA" => RecordKind::Host,
+            "SERVICEPERFDATA" => RecordKind::Service,
+            other => return Err(PerfdataRecordError::InvalidDataType(other.to_string())),
+        };
+
+        let timet = require("TIMET")?;
+        let timestamp = timet
+            .parse()
+            .map_err(|_| PerfdataRecordError::InvalidTimestamp(timet.to_string()))?;
+
+        let service = match kind {
+            RecordKind::Host => None,
+            RecordKind::Service => Some(Cow::Borrowed(require("SERVICEDESC")?)),
+        };
+
+        let prefixed = |key: &str| get(&format!("{}{}", kind.prefix(), key)).map(Cow::Borrowed);
+
+        let perfdata = match get(kind.data_type()) {
+            Some(data) => PerfdataSet::parse_with(data, options)
+                .map_err(|e| e.with_offset(offset_of(value, data).unwrap_or_default()))?,
+            None => PerfdataSet::new(),
+        };
+
+        Ok(PerfdataRecord {
+            kind,
+            timestamp,
+            host: Cow::Borrowed(require("HOSTNAME")?),
+            service,
+            check_command: prefixed("CHECKCOMMAND"),
+            state: prefixed("STATE"),
+            state_type: prefixed("STATETYPE"),
+            perfdata,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a str> for PerfdataRecord<'a> {
+    type Error = PerfdataRecordError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        PerfdataRecord::parse_with(value, &ParseOptions::default())
+    }
+}
+
+impl<'a> TryFrom<&'a String> for PerfdataRecord<'a> {
+    type Error = PerfdataRecordError;
+
+    fn try_from(value: &'a String) -> Result<Self, Self::Error> {
+        PerfdataRecord::try_from(value.as_str())
+    }
+}
+
+impl TryFrom<String> for PerfdataRecord<'static> {
+    type Error = PerfdataRecordError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        PerfdataRecord::try_from(value.as_str()).map(PerfdataRecord::into_owned)
+    }
+}
+
+impl Display for PerfdataRecord<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DATATYPE{}{}", KEY_DELIMITER, self.kind.data_type())?;
+
+        let prefix = self.kind.prefix();
+        let mut write_field = |key: &str, value: &dyn Display| {
+            write!(f, "{}{}{}{}", FIELD_DELIMITER, key, KEY_DELIMITER, value)
+        };
+
+        write_field("TIMET", &self.timestamp)?;
+        write_field("HOSTNAME", &self.host)?;
+        if let Some(service) = &self.service {
+            write_field("SERVICEDESC", service)?;
+        }
+        write_field(self.kind.data_type(), &self.perfdata)?;
+
+        let optional = [
+            ("CHECKCOMMAND", &self.check_command),
+            ("STATE", &self.state),
+            ("STATETYPE", &self.state_type),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                write_field(&format!("{}{}", prefix, key), value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `PerfdataRecordReader` reads [PerfdataRecord]s from a perfdata file line by line, keeping only
+/// a single line in memory at once.
+///
+/// Empty lines are skipped. Errors contain the line number and do not end the iteration.
+pub struct PerfdataRecordReader<R> {
+    lines: LineReader<R>,
+    options: ParseOptions,
+}
+
+impl<R: BufRead> PerfdataRecordReader<R> {
+    /// Create a new `PerfdataRecordReader` reading from the given [BufRead]
+    pub fn new(reader: R) -> Self {
+        PerfdataRecordReader {
+            lines: LineReader::new(reader),
+            options: ParseOptions::default(),
+        }
+    }
+
+    /// Use the given [ParseOptions] for parsing the perfdata of each record
+    #[must_use]
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for PerfdataRecordReader<R> {
+    type Item = Result<PerfdataRecord<'static>, PerfdataReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (line_number, line) = match self.lines.next_line()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let parsed = PerfdataRecord::parse_with(line, &self.options)
+                .map(PerfdataRecord::into_owned)
+                .map_err(|source| PerfdataReadError::Record {
+                    line: line_number,
+                    source,
+                });
+            return Some(parsed);
+        }
+    }
+}
+
+/// `PerfdataRecordWriter` writes [PerfdataRecord]s to a perfdata file, one per line
+pub struct PerfdataRecordWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> PerfdataRecordWriter<W> {
+    /// Create a new `PerfdataRecordWriter` writing to the given [Write]
+    pub fn new(writer: W) -> Self {
+        PerfdataRecordWriter { writer }
+    }
+
+    /// Write a single [PerfdataRecord] as a line
+    pub fn write(&mut self, record: &PerfdataRecord) -> std::io::Result<()> {
+        writeln!(self.writer, "{}", record)
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PerfdataParseErrorKind;
+    use crate::{Perfdata, ThresholdRange};
+    use std::io::Cursor;
+
+    const SERVICE_RECORD: &str = "DATATYPE::SERVICEPERFDATA\tTIMET::1700000000\tHOSTNAME::web01\tSERVICEDESC::Disk /\tSERVICEPERFDATA::/=2643MB;5948;5958;0;5968\tSERVICECHECKCOMMAND::check_disk!20%!10%\tHOSTSTATE::UP\tHOSTSTATETYPE::HARD\tSERVICESTATE::OK\tSERVICESTATETYPE::HARD";
+
+    #[test]
+    fn test_parse_service_record() {
+        let record = PerfdataRecord::try_from(SERVICE_RECORD).unwrap();
+        let expected: PerfdataSet = vec![Perfdata::megabytes("/", 2643)
+            .with_warn(ThresholdRange::above_pos(5948))
+            .with_crit(ThresholdRange::above_pos(5958))
+            .with_min(0)
+            .with_max(5968)]
+        .into();
+
+        assert_eq!(record.kind(), RecordKind::Service);
+        assert_eq!(record.timestamp(), 1700000000);
+        assert_eq!(record.host_name(), "web01");
+        assert_eq!(record.service_description(), Some("Disk /"));
+        assert_eq!(record.check_command(), Some("check_disk!20%!10%"));
+        assert_eq!(record.state(), Some("OK"));
+        assert_eq!(record.service_status(), Some(MonitoringStatus::OK));
+        assert_eq!(record.host_status(), None);
+        assert_eq!(record.state_type(), Some("HARD"));
+        assert_eq!(record.perfdata(), &expected);
+    }
+
+    #[test]
+    fn test_parse_host_record() {
+        let line = "DATATYPE::HOSTPERFDATA\tTIMET::1700000000\tHOSTNAME::web01\tHOSTPERFDATA::\tHOSTCHECKCOMMAND::check-host-alive\tHOSTSTATE::DOWN\tHOSTSTATETYPE::SOFT";
+
+        let record = PerfdataRecord::try_from(line).unwrap();
+
+        assert_eq!(record.kind(), RecordKind::Host);
+        assert_eq!(record.service_description(), None);
+        assert_eq!(record.check_command(), Some("check-host-alive"));
+        assert_eq!(record.state(), Some("DOWN"));
+        assert_eq!(record.host_status(), Some(HostStatus::Down));
+        assert_eq!(record.service_status(), None);
+        assert!(record.perfdata().is_empty());
+        assert_eq!(record.to_string(), line);
+    }
+
+    #[test]
+    fn test_parse_record_errors() {
+        let missing = "DATATYPE::SERVICEPERFDATA\tTIMET::1700000000\tHOSTNAME::web01";
+        let timestamp = "DATATYPE::HOSTPERFDATA\tTIMET::yesterday\tHOSTNAME::web01";
+        let data_type = "DATATYPE::FOO\tTIMET::1\tHOSTNAME::web01";
+        let perfdata = "DATATYPE::HOSTPERFDATA\tTIMET::1\tHOSTNAME::a\tHOSTPERFDATA::rta=x;";
+
+        let err = PerfdataRecord::try_from(perfdata).unwrap_err();
+
+        assert_eq!(
+            PerfdataRecord::try_from(missing),
+            Err(PerfdataRecordError::MissingField("SERVICEDESC"))
+        );
+        assert_eq!(
+            PerfdataRecord::try_from(timestamp),
+            Err(PerfdataRecordError::InvalidTimestamp(
+                "yesterday".to_string()
+            ))
+        );
+        assert_eq!(
+            PerfdataRecord::try_from(data_type),
+            Err(PerfdataRecordError::InvalidDataType("FOO".to_string()))
+        );
+        match err {
+            PerfdataRecordError::Perfdata(e) => {
+                assert!(matches!(
+                    e.kind(),
+                    PerfdataParseErrorKind::ParseValueError(_)
+                ));
+                assert_eq!(&perfdata[e.span()], "x");
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_and_write_records() {
+        let record = PerfdataRecord::service(1700000000, "web01", "load")
+            .with_check_command("check_load")
+            .with_state("WARNING")
+            .with_state_type("SOFT")
+            .with_perfdata(vec![Perfdata::unitless("load1", 4.5)].into());
+        let mut writer = PerfdataRecordWriter::new(Vec::new());
+        writer.write(&record).unwrap();
+        writer
+            .write(&PerfdataRecord::host(1700000001, "web02"))
+            .unwrap();
+        let mut written = writer.into_inner();
+        written.extend_from_slice(b"\nDATATYPE::HOSTPERFDATA\n");
+
+        let results: Vec<_> = PerfdataRecordReader::new(Cursor::new(written)).collect();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &record);
+        assert_eq!(results[1].as_ref().unwrap().host_name(), "web02");
+        assert_eq!(results[2].as_ref().unwrap_err().line(), 4);
+    }
+}
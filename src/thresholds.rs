@@ -1,4 +1,4 @@
-use crate::perf::Value;
+use crate::perf::{NumberFormat, Unit, Value};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
@@ -72,6 +72,22 @@ impl ThresholdRange {
         Self::new(true, start.into(), end.into())
     }
 
+    /// The lower bound of the range, may be negative infinity
+    pub fn start(&self) -> Value {
+        self.start
+    }
+
+    /// The upper bound of the range, may be infinity
+    pub fn end(&self) -> Value {
+        self.end
+    }
+
+    /// Returns true if an alert is raised for values inside the range (`@` notation),
+    /// false if an alert is raised for values outside the range
+    pub fn alert_inside(&self) -> bool {
+        self.alert_inside
+    }
+
     /// This returns true if the given value lies inside the given Threshold and
     /// as such should produce a critical, or warning response.
     /// (See also [with_crit()](`crate::Perfdata::with_crit()`) and [with_warn()](`crate::Perfdata::with_warn()`).
@@ -85,24 +101,64 @@ impl ThresholdRange {
             !is_inside
         }
     }
+
+    // The limit of a range from 0 to a finite end, which is written as plain number like `10`
+    pub(crate) fn upper_limit(&self) -> Option<Value> {
+        let plain = !self.alert_inside && self.start == 0.0 && self.end.is_finite();
+        plain.then_some(self.end)
+    }
+
+    // Applies the function to both bounds, e.g. to convert them into another unit
+    pub(crate) fn map<F: Fn(Value) -> Value>(self, f: F) -> Self {
+        Self::new(self.alert_inside, f(self.start), f(self.end))
+    }
 }
 
-impl Display for ThresholdRange {
+impl ThresholdRange {
+    // The unit is needed to format the bounds of thresholds for counters
     // this could be avoided with `(start,end) if start == foo && end == bar` but it's a lot uglier
     #[allow(illegal_floating_point_literal_pattern)]
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    pub(crate) fn fmt_with(
+        &self,
+        f: &mut Formatter<'_>,
+        format: &NumberFormat,
+        unit: &Unit,
+    ) -> std::fmt::Result {
         let inside = if self.alert_inside { "@" } else { "" };
+        let number = |f: &mut Formatter<'_>, value| format.fmt_number(f, value, unit);
 
         match (self.start, self.end) {
             (Value::NEG_INFINITY, Value::INFINITY) => write!(f, "{}~:", inside),
             (0.0, Value::INFINITY) => write!(f, "{}0:", inside),
-            (0.0, end) => write!(f, "{}{}", inside, end),
-            (Value::NEG_INFINITY, end) => write!(f, "{}~:{}", inside, end),
-            (start, end) => write!(f, "{}{}:{}", inside, start, end),
+            (0.0, end) => {
+                write!(f, "{}", inside)?;
+                number(f, end)
+            }
+            (Value::NEG_INFINITY, end) => {
+                write!(f, "{}~:", inside)?;
+                number(f, end)
+            }
+            (start, Value::INFINITY) => {
+                write!(f, "{}", inside)?;
+                number(f, start)?;
+                write!(f, ":")
+            }
+            (start, end) => {
+                write!(f, "{}", inside)?;
+                number(f, start)?;
+                write!(f, ":")?;
+                number(f, end)
+            }
         }
     }
 }
 
+impl Display for ThresholdRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with(f, &NumberFormat::default(), &Unit::None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,6 +212,24 @@ mod tests {
         assert!(inside_10_20_between);
     }
 
+    #[test]
+    fn test_accessors() {
+        let above_pos_10 = ThresholdRange::above_pos(10);
+        let above_10 = ThresholdRange::above(10);
+        let inside_20_10 = ThresholdRange::inside(20, 10);
+
+        assert_eq!(above_pos_10.start(), 0.0);
+        assert_eq!(above_pos_10.end(), 10.0);
+        assert!(!above_pos_10.alert_inside());
+
+        assert_eq!(above_10.start(), Value::NEG_INFINITY);
+        assert_eq!(above_10.end(), 10.0);
+
+        assert_eq!(inside_20_10.start(), 10.0);
+        assert_eq!(inside_20_10.end(), 20.0);
+        assert!(inside_20_10.alert_inside());
+    }
+
     #[test]
     fn test_alert_boundaries() {
         let outside_10_20 = ThresholdRange::outside(10, 20);
@@ -173,4 +247,23 @@ mod tests {
         assert!(inside_10_10);
         assert!(inside_10_20);
     }
+
+    #[test]
+    fn test_display() {
+        let ranges = [
+            ThresholdRange::above_pos(10),
+            ThresholdRange::below(10),
+            ThresholdRange::above(10),
+            ThresholdRange::outside(10, 20),
+            ThresholdRange::inside(10, 20),
+            ThresholdRange::inside(f64::NEG_INFINITY, f64::INFINITY),
+        ];
+
+        let formatted: Vec<String> = ranges.iter().map(|r| r.to_string()).collect();
+
+        assert_eq!(formatted, ["10", "10:", "~:10", "10:20", "@10:20", "@~:"]);
+        for (range, formatted) in ranges.iter().zip(formatted) {
+            assert_eq!(formatted.parse::<ThresholdRange>().as_ref(), Ok(range));
+        }
+    }
 }